@@ -0,0 +1,244 @@
+use super::{common::Separator, headers::is_header_name};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    braced, bracketed, parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Expr, Ident, LitStr, Token,
+};
+
+syn::custom_keyword!(env);
+
+/// Credentials of an API, sent with every request.
+///
+/// ```rust
+/// auth: Bearer(env "TOKEN")
+/// auth: Basic(user, &password)
+/// auth: ApiKey { in: header, name: "X-MBX-APIKEY", from: env "BINANCE_API" }
+/// auth: ApiKey { in: query, name: "api_key", from: env "FRED_API" }
+/// auth: OAuth2 {
+///     token_url: "https://auth.example.com/token",
+///     client_id: env "CLIENT_ID",
+///     client_secret: env "CLIENT_SECRET",
+///     scopes: ["read", "write"],              // optional
+///     refresh_token: env "REFRESH_TOKEN",     // optional
+/// }
+/// ```
+///
+/// Each secret is an environment variable (`env "NAME"`), or any expression of a `&str` or
+/// `String`; it's read once, when the API is built.
+pub enum Auth {
+    Bearer(Secret),
+    Basic(Secret, Secret),
+    ApiKey {
+        location: Ident, // `header` or `query`
+        name: LitStr,
+        key: Secret,
+    },
+    OAuth2 {
+        token_url: Secret,
+        client_id: Secret,
+        client_secret: Secret,
+        scopes: Vec<Expr>,
+        refresh_token: Option<Secret>,
+    },
+}
+
+/// `env "NAME"`, or an expression.
+pub enum Secret {
+    Env(LitStr),
+    Expr(Box<Expr>),
+}
+
+impl Parse for Secret {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(env) && input.peek2(LitStr) {
+            input.parse::<env>()?;
+            return Ok(Self::Env(input.parse()?));
+        }
+        Ok(Self::Expr(Box::new(input.parse()?)))
+    }
+}
+
+impl Parse for Auth {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let scheme: Ident = input.parse()?;
+        let content;
+        match scheme.to_string().as_str() {
+            // `Bearer(token)`
+            "Bearer" => {
+                parenthesized!(content in input);
+                Ok(Self::Bearer(content.parse()?))
+            }
+
+            // `Basic(user, password)`
+            "Basic" => {
+                parenthesized!(content in input);
+                let user = content.parse()?;
+                content.parse::<Token![,]>()?;
+                let password = content.parse()?;
+                content.parse::<Option<Token![,]>>()?;
+                Ok(Self::Basic(user, password))
+            }
+
+            // `ApiKey { in: header, name: "X-Api-Key", from: key }`
+            "ApiKey" => {
+                braced!(content in input);
+                let mut location: Option<Ident> = None;
+                let mut name: Option<LitStr> = None;
+                let mut key: Option<Secret> = None;
+                while !content.is_empty() {
+                    if content.peek(Token![in]) {
+                        content.parse::<Token![in]>()?;
+                        content.parse::<Separator>()?;
+                        let ident: Ident = content.parse()?;
+                        if ident != "header" && ident != "query" {
+                            return Err(syn::Error::new(
+                                ident.span(),
+                                "an API key is sent `in` a `header`, or the `query`",
+                            ));
+                        }
+                        location = Some(ident);
+                    } else {
+                        let field: Ident = content.parse()?;
+                        content.parse::<Separator>()?;
+                        match field.to_string().as_str() {
+                            "name" => name = Some(content.parse()?),
+                            "from" => key = Some(content.parse()?),
+                            _ => {
+                                return Err(syn::Error::new(
+                                    field.span(),
+                                    "unknown ApiKey input; expected one of `in`, `name`, or `from`",
+                                ))
+                            }
+                        }
+                    }
+                    content.parse::<Option<Token![,]>>()?;
+                }
+
+                let (Some(location), Some(name), Some(key)) = (location, name, key) else {
+                    return Err(syn::Error::new(
+                        scheme.span(),
+                        "ApiKey requires `in`, `name`, and `from`",
+                    ));
+                };
+                if location == "header" && !is_header_name(&name.value()) {
+                    return Err(syn::Error::new(name.span(), "invalid header name"));
+                }
+                Ok(Self::ApiKey {
+                    location,
+                    name,
+                    key,
+                })
+            }
+
+            // `OAuth2 { token_url: "..", client_id: id, client_secret: secret, .. }`
+            "OAuth2" => {
+                braced!(content in input);
+                let mut token_url: Option<Secret> = None;
+                let mut client_id: Option<Secret> = None;
+                let mut client_secret: Option<Secret> = None;
+                let mut scopes: Vec<Expr> = vec![];
+                let mut refresh_token: Option<Secret> = None;
+                while !content.is_empty() {
+                    let field: Ident = content.parse()?;
+                    content.parse::<Separator>()?;
+                    match field.to_string().as_str() {
+                        "token_url" => token_url = Some(content.parse()?),
+                        "client_id" => client_id = Some(content.parse()?),
+                        "client_secret" => client_secret = Some(content.parse()?),
+                        "refresh_token" => refresh_token = Some(content.parse()?),
+                        "scopes" => {
+                            let list;
+                            bracketed!(list in content);
+                            scopes.extend(Punctuated::<Expr, Token![,]>::parse_terminated(&list)?);
+                        }
+                        _ => {
+                            return Err(syn::Error::new(
+                                field.span(),
+                                "unknown OAuth2 input; expected one of `token_url`, `client_id`, \
+                                 `client_secret`, `scopes`, or `refresh_token`",
+                            ))
+                        }
+                    }
+                    content.parse::<Option<Token![,]>>()?;
+                }
+
+                let (Some(token_url), Some(client_id), Some(client_secret)) =
+                    (token_url, client_id, client_secret)
+                else {
+                    return Err(syn::Error::new(
+                        scheme.span(),
+                        "OAuth2 requires `token_url`, `client_id`, and `client_secret`",
+                    ));
+                };
+                Ok(Self::OAuth2 {
+                    token_url,
+                    client_id,
+                    client_secret,
+                    scopes,
+                    refresh_token,
+                })
+            }
+            _ => Err(syn::Error::new(
+                scheme.span(),
+                "unknown auth scheme; expected one of `Bearer`, `Basic`, `ApiKey`, or `OAuth2`",
+            )),
+        }
+    }
+}
+
+impl Auth {
+    // e.g., `kvapi::Auth::query("api_key", kvapi::auth::env("FRED_API")?)`;
+    // evaluated in a closure returning any error
+    pub(crate) fn build(&self) -> TokenStream {
+        match self {
+            Self::Bearer(token) => {
+                let token = token.build();
+                quote!( kvapi::Auth::bearer(#token) )
+            }
+            Self::Basic(user, password) => {
+                let (user, password) = (user.build(), password.build());
+                quote!( kvapi::Auth::basic(#user, #password) )
+            }
+            Self::ApiKey {
+                location,
+                name,
+                key,
+            } => {
+                let key = key.build();
+                quote!( kvapi::Auth::#location(#name, #key) )
+            }
+            Self::OAuth2 {
+                token_url,
+                client_id,
+                client_secret,
+                scopes,
+                refresh_token,
+            } => {
+                let (url, id, secret) =
+                    (token_url.build(), client_id.build(), client_secret.build());
+                let scopes = (!scopes.is_empty()).then(|| quote!( .scopes([#( #scopes ),*]) ));
+                let refresh_token = refresh_token.as_ref().map(|refresh_token| {
+                    let refresh_token = refresh_token.build();
+                    quote!( .refresh_token(#refresh_token) )
+                });
+                quote! {
+                    kvapi::Auth::oauth2(
+                        kvapi::OAuth2::new(#url, #id, #secret) #scopes #refresh_token
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl Secret {
+    fn build(&self) -> TokenStream {
+        match self {
+            Self::Env(name) => quote!( kvapi::auth::env(#name)? ),
+            Self::Expr(expr) => quote!( #expr ),
+        }
+    }
+}
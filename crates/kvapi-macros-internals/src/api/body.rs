@@ -0,0 +1,54 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Expr, Ident};
+
+/// Encoding of a Dict entry's request body, set with the `body` attribute.
+///
+/// ```rust
+/// "orders": CreateOrder => OrderAck,     // json, by default
+///
+/// #[body: form]
+/// "token": TokenForm => Token,
+///
+/// #[body: bytes, method: PUT]
+/// "upload/{name}": Vec<u8> => Uploaded,
+/// ```
+///
+/// The body type is taken by reference, i.e., `api.orders.post(&order)`; it must be
+/// `Serialize` for `json` & `form`, and `AsRef<[u8]>` for `bytes`. Entries without a body
+/// type take any `&kvapi::Value`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Json,
+    Form,
+    Bytes,
+}
+
+impl Encoding {
+    /// Parse one of `json`, `form`, or `bytes`.
+    pub fn parse(arg: Expr) -> syn::Result<Self> {
+        let ident = syn::parse2::<Ident>(arg.into_token_stream())?;
+        match ident.to_string().as_str() {
+            "json" => Ok(Self::Json),
+            "form" => Ok(Self::Form),
+            "bytes" => Ok(Self::Bytes),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                "unknown body encoding; expected one of `json`, `form`, or `bytes`",
+            )),
+        }
+    }
+
+    // set the `body` argument on a `kvapi::RequestBuilder`
+    pub(crate) fn apply(&self) -> TokenStream {
+        match self {
+            Self::Json => quote!( .json(body) ),
+            Self::Form => quote!( .form(body) ),
+            Self::Bytes => quote! {
+                .header("content-type", "application/octet-stream")
+                .body(AsRef::<[u8]>::as_ref(body).to_vec())
+            },
+        }
+    }
+}
@@ -1,19 +1,41 @@
-use super::{common::Separator, dict::Dict, headers::Headers};
+use super::{
+    auth::Auth,
+    cache::Cache,
+    common::{build_duration, duration, Separator},
+    dict::Dict,
+    envelope::Envelope,
+    headers::Headers,
+    rate_limit::RateLimit,
+    retry::Retry,
+    ws::Ws,
+};
 use convert_case::{Case, Casing};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::{
+    braced, bracketed,
     parse::{Parse, ParseStream},
-    Expr, Ident, LitStr,
+    punctuated::Punctuated,
+    Expr, Ident, LitStr, Token, Type,
 };
 
 /// Input for the `api! { #input }` macro.
 ///
 /// ```rust
 /// name:       PascalStructName
-/// dict:       { "endpoint": Type }
+/// dict:       { "endpoint/{path_param}": Type }
 /// headers:    { "Header Name": "Header Value" }
-/// query:      "?query_param"
+/// auth:       ApiKey { in: query, name: "api_key", from: env "FRED_API" }
+/// query:      "global_param=value"
+/// error:      ErrorType
+/// envelope:   { data: "result", error: "error" }
+/// middleware: [Logging, Signer::new(key)]
+/// rate_limit: 1200/min
+/// retry:      { attempts: 5, backoff: exponential(200ms, 10s), on: [429, 500..=599, timeout] }
+/// timeout:    10s
+/// connect_timeout: 3s
+/// cache:      10m
+/// ws:         { url: "wss://stream.binance.com:9443/ws", streams: { "{symbol}@trade": Trade } }
 /// ```
 ///
 /// The Director will generate the API with an ApiBuilder.
@@ -25,7 +47,17 @@ pub struct ApiBuilder {
     // optional
     pub base: Option<TokenStream>,
     pub headers: Option<Headers>,
+    pub auth: Option<Auth>,
     pub query: Option<Expr>,
+    pub error: Option<Type>, // body of non-2xx responses
+    pub envelope: Option<Envelope>,
+    pub middleware: Vec<Expr>, // run around every request, in order
+    pub rate_limit: Option<RateLimit>,
+    pub retry: Option<Retry>,
+    pub timeout: Option<u64>,         // milliseconds, per request
+    pub connect_timeout: Option<u64>, // milliseconds; of the API's own client
+    pub cache: Option<Cache>,
+    pub ws: Option<Ws>, // needs the `ws` feature of `kvapi`
 }
 
 impl ApiBuilder {
@@ -52,26 +84,27 @@ impl ApiBuilder {
             // api.nodes
             let fields = node.children_fields(api_name.clone());
             if node.is_http() {
-                let url = node.build_url(self.base.clone());
-                let http = node.build_http(url, self.headers.clone());
+                let request = format_ident!("{}Request", pascal);
+                let url = node.build_url(self.base.clone(), self.query.clone());
+                let http = node.build_http(url, &request);
+                let request = node.build_request(&pascal, &request, self.headers.clone());
 
-                // http node
+                // http node; borrows the client of the root
                 let node = quote! {
                     pub struct #pascal {
-                        client: kvapi::Client,
-                        url: String,
+                        api: std::sync::Arc<kvapi::Api>,
                         #( pub #fields, )*
                     }
                     impl #pascal {
-                        pub fn new() -> Self {
+                        fn new(api: &std::sync::Arc<kvapi::Api>) -> Self {
                             Self {
-                                client: Self::build_client().unwrap(),
-                                url: Self::build_url(),
-                                #( #fields::new(), )*
+                                api: api.clone(),
+                                #( #fields::new(api), )*
                             }
                         }
                         #http
                     }
+                    #request
                 };
 
                 nodes.push(node)
@@ -83,9 +116,9 @@ impl ApiBuilder {
                     }
 
                     impl #pascal {
-                        pub fn new() -> Self {
+                        fn new(api: &std::sync::Arc<kvapi::Api>) -> Self {
                             Self {
-                                #( #fields::new(), )*
+                                #( #fields::new(api), )*
                             }
                         }
                     }
@@ -94,19 +127,183 @@ impl ApiBuilder {
             }
         }
 
+        // the API's headers, sent with every request
+        let mut headers = self
+            .headers
+            .map(|headers| headers.client)
+            .unwrap_or_default();
+
+        // any error names the API, i.e., "failed to build `Sec`: invalid header `User-Agent`: .."
+        if !headers.is_empty() {
+            headers.insert(0, quote!( let api = stringify!(#api_name); ));
+        }
+
+        // the API's own error type, for non-2xx responses
+        let error = self.error.map(|error| quote!( .with_error::<#error>() ));
+        let envelope = self.envelope.map(|envelope| {
+            let envelope = envelope.build();
+            quote!( .with_envelope(#envelope) )
+        });
+        let middleware = &self.middleware;
+        let rate_limit = self.rate_limit.map(|rate_limit| {
+            let rate_limit = rate_limit.build();
+            quote!( .with_rate_limit(#rate_limit) )
+        });
+        let retry = self.retry.map(|retry| {
+            let retry = retry.build();
+            quote!( .with_retry(#retry) )
+        });
+        let timeout = self.timeout.map(|timeout| {
+            let timeout = build_duration(timeout);
+            quote!( .with_timeout(#timeout) )
+        });
+        let cache = self.cache.map(|cache| {
+            let cache = cache.build();
+            quote!( .with_cache(#cache) )
+        });
+        let connect_timeout = self.connect_timeout.map(|timeout| {
+            let timeout = build_duration(timeout);
+            quote!( .connect_timeout(#timeout) )
+        });
+
+        // the API's WebSocket feed, as `api.ws`; rebuilt with the API, keeping its URL
+        let (ws, ws_field, ws_new, ws_url, rebuild) = match &self.ws {
+            Some(ws) => {
+                let name = format_ident!("{}Ws", api_name);
+                let nodes = ws.build(&name);
+                let socket = ws.build_socket();
+                let function = quote! {
+                    /// Connect `ws` to `url` instead, i.e., a testnet.
+                    pub fn with_ws_url(mut self, url: impl Into<String>) -> Self {
+                        self.ws = #name::new(Self::socket().url(url));
+                        self
+                    }
+
+                    fn socket() -> kvapi::Socket {
+                        #socket
+                    }
+                };
+                (
+                    Some(nodes),
+                    Some(quote!( pub ws: #name, )),
+                    Some(quote!( ws: #name::new(Self::socket()), )),
+                    Some(function),
+                    quote!( Self { ws: self.ws, ..Self::from_api(api) } ),
+                )
+            }
+            None => (None, None, None, None, quote!(Self::from_api(api))),
+        };
+
+        // the API's credentials; any error names the API, as for headers
+        let (with_auth, auth) = match self.auth {
+            Some(auth) => {
+                let auth = auth.build();
+                let function = quote! {
+                    fn auth() -> kvapi::Result<kvapi::Auth> {
+                        let api = stringify!(#api_name);
+                        (|| -> std::result::Result<kvapi::Auth, Box<dyn std::error::Error + Send + Sync>> {
+                            Ok(#auth)
+                        })()
+                        .map_err(|source| kvapi::Error::Auth { api, source })
+                    }
+                };
+                (Some(quote!( .with_auth(Self::auth()?) )), Some(function))
+            }
+            None => (None, None),
+        };
+
         // return the final TokenStream
         quote! {
             pub struct #api_name {
+                api: std::sync::Arc<kvapi::Api>,
                 #( pub #fields, )*
+                #ws_field
             }
             impl #api_name {
+                /// Panics if the API can't be built; see `try_new()`.
                 pub fn new() -> Self {
+                    Self::try_new().unwrap_or_else(|e| panic!("{:#}", e))
+                }
+
+                /// Build the API, i.e., its client & headers.
+                pub fn try_new() -> kvapi::Result<Self> {
+                    Self::build(None)
+                }
+
+                /// Use the given client, i.e., to share one connection pool across several APIs;
+                /// its own `connect_timeout`, if any, is used.
+                ///
+                /// Panics if the API can't be built; see `try_with_client()`.
+                pub fn with_client(client: kvapi::Client) -> Self {
+                    Self::try_with_client(client).unwrap_or_else(|e| panic!("{:#}", e))
+                }
+
+                pub fn try_with_client(client: kvapi::Client) -> kvapi::Result<Self> {
+                    Self::build(Some(client))
+                }
+
+                fn build(client: Option<kvapi::Client>) -> kvapi::Result<Self> {
+                    let client = match client {
+                        Some(client) => client,
+                        None => kvapi::ClientBuilder::new() #connect_timeout .build()?,
+                    };
+                    let api = kvapi::Api::new(client, Self::headers()?)
+                        #error
+                        #envelope
+                        #with_auth
+                        #( .with_middleware(#middleware) )*
+                        #rate_limit
+                        #retry
+                        #timeout
+                        #cache;
+                    Ok(Self::from_api(api))
+                }
+
+                // every endpoint shares the one `kvapi::Api`
+                fn from_api(api: kvapi::Api) -> Self {
+                    let api = std::sync::Arc::new(api);
                     Self {
-                        #( #fields::new(), )*
+                        #( #fields::new(&api), )*
+                        #ws_new
+                        api,
                     }
                 }
+
+                fn headers() -> kvapi::Result<kvapi::HeaderMap> {
+                    let mut headers = kvapi::HeaderMap::new();
+                    #( #headers )*
+                    Ok(headers)
+                }
+
+                #auth
+
+                #ws_url
+
+                /// Run `middleware` around every request, after any given in `api!`.
+                pub fn with_middleware(self, middleware: impl kvapi::Middleware) -> Self {
+                    let api = kvapi::Api::clone(&self.api).with_middleware(middleware);
+                    #rebuild
+                }
+
+                /// Take the weight of every request from `rate_limit`, rather than any given in
+                /// `api!`; i.e., to share one limit across several APIs of a host.
+                pub fn with_rate_limit(self, rate_limit: kvapi::RateLimit) -> Self {
+                    let api = kvapi::Api::clone(&self.api).with_rate_limit(rate_limit);
+                    #rebuild
+                }
+
+                /// The rate limit shared by every endpoint, if any.
+                pub fn rate_limit(&self) -> Option<&kvapi::RateLimit> {
+                    self.api.rate_limit()
+                }
+
+                /// The HTTP client shared by every endpoint.
+                pub fn client(&self) -> &kvapi::Client {
+                    self.api.client()
+                }
             }
             #( #nodes )*
+            #ws
         }
     }
 }
@@ -121,7 +318,17 @@ impl Parse for ApiBuilder {
             // optional
             base: None,
             headers: None,
+            auth: None,
             query: None,
+            error: None,
+            envelope: None,
+            middleware: vec![],
+            rate_limit: None,
+            retry: None,
+            timeout: None,
+            connect_timeout: None,
+            cache: None,
+            ws: None,
         };
 
         while !input.is_empty() {
@@ -145,10 +352,50 @@ impl Parse for ApiBuilder {
                     let headers: Headers = input.parse()?;
                     api.headers = Some(headers);
                 }
+                "auth" => {
+                    let auth: Auth = input.parse()?;
+                    api.auth = Some(auth);
+                }
                 "query" | "Q" => {
                     let query: Expr = input.parse()?;
                     api.query = Some(query);
                 }
+                "error" | "E" => {
+                    let error: Type = input.parse()?;
+                    api.error = Some(error);
+                }
+                "envelope" => {
+                    let content;
+                    braced!(content in input);
+                    let envelope: Envelope = content.parse()?;
+                    api.envelope = Some(envelope);
+                }
+                "middleware" => {
+                    let content;
+                    bracketed!(content in input);
+                    let middleware = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?;
+                    api.middleware.extend(middleware);
+                }
+                "rate_limit" => {
+                    let rate_limit: RateLimit = input.parse()?;
+                    api.rate_limit = Some(rate_limit);
+                }
+                "retry" => {
+                    let content;
+                    braced!(content in input);
+                    let retry: Retry = content.parse()?;
+                    api.retry = Some(retry);
+                }
+                "timeout" => api.timeout = Some(duration(&input.parse()?)?),
+                "connect_timeout" => api.connect_timeout = Some(duration(&input.parse()?)?),
+                "cache" => {
+                    let cache: Cache = input.parse()?;
+                    api.cache = Some(cache);
+                }
+                "ws" => {
+                    let ws: Ws = input.parse()?;
+                    api.ws = Some(ws);
+                }
                 _ => return Err(syn::Error::new(ident.span(), "unknown input to `api!`")),
             }
         }
@@ -0,0 +1,72 @@
+use super::common::{build_duration, duration, Separator};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    Expr, Ident, LitInt, Token,
+};
+
+/// The response cache of an API.
+///
+/// ```rust
+/// cache: 10m                                                   // every `GET`, in memory
+/// cache: { ttl: 1h, store: kvapi::cache::Disk::new(".cache") }
+/// cache: { store: kvapi::cache::Memory::new(1024) }            // only entries with `#[cache]`
+/// ```
+///
+/// The ttl is in milliseconds; a Dict entry's `#[cache: 1d]` replaces it.
+pub struct Cache {
+    pub ttl: Option<u64>,
+    pub store: Option<Expr>,
+}
+
+impl Parse for Cache {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitInt) {
+            let ttl = duration(&input.parse()?)?;
+            return Ok(Self {
+                ttl: Some(ttl),
+                store: None,
+            });
+        }
+
+        let content;
+        braced!(content in input);
+        let mut cache = Self {
+            ttl: None,
+            store: None,
+        };
+        while !content.is_empty() {
+            let key: Ident = content.parse()?;
+            content.parse::<Separator>()?;
+            match key.to_string().as_str() {
+                "ttl" => cache.ttl = Some(duration(&content.parse()?)?),
+                "store" => cache.store = Some(content.parse()?),
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown cache input; expected one of `ttl`, or `store`",
+                    ))
+                }
+            }
+            content.parse::<Option<Token![,]>>()?;
+        }
+        Ok(cache)
+    }
+}
+
+impl Cache {
+    // e.g., `kvapi::Cache::new(kvapi::cache::Disk::new(".cache")).ttl(..)`
+    pub(crate) fn build(&self) -> TokenStream {
+        let cache = match &self.store {
+            Some(store) => quote!( kvapi::Cache::new(#store) ),
+            None => quote!(kvapi::Cache::default()),
+        };
+        let ttl = self.ttl.map(|ttl| {
+            let ttl = build_duration(ttl);
+            quote!( .ttl(#ttl) )
+        });
+        quote!( #cache #ttl )
+    }
+}
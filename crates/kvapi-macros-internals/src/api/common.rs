@@ -1,7 +1,9 @@
+use proc_macro2::{Group, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
 use std::collections::HashSet;
 use syn::{
     parse::{Parse, ParseStream},
-    Token,
+    LitInt, Token,
 };
 
 /// Seperator token; one of: `:`, `=`, `->`, or `=>`.
@@ -35,10 +37,68 @@ impl Parse for Separator {
 /// Remember the file types to ignore when parsing the endpoint.
 pub fn file_types() -> HashSet<&'static str> {
     let mut set: HashSet<&str> = HashSet::new();
-    vec!["json", "csv", "xml", "toml", "yaml", "html", "htm"]
+    ["json", "csv", "xml", "toml", "yaml", "yml", "html", "htm"]
         .iter()
         .for_each(|file_type| {
             set.insert(file_type);
         });
     set
 }
+
+/// A duration, i.e., `200ms`, `10s`, `5m`, `1h`, or `1d`; in milliseconds.
+pub fn duration(lit: &LitInt) -> syn::Result<u64> {
+    let unit = match lit.suffix() {
+        "ms" => 1,
+        "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        "d" => 24 * 60 * 60 * 1000,
+        _ => {
+            return Err(syn::Error::new(
+                lit.span(),
+                "expected a duration, i.e., `200ms`, `10s`, `5m`, `1h`, or `1d`",
+            ))
+        }
+    };
+    Ok(lit.base10_parse::<u64>()? * unit)
+}
+
+// e.g., `std::time::Duration::from_millis(200)`
+pub(crate) fn build_duration(millis: u64) -> TokenStream {
+    quote!( std::time::Duration::from_millis(#millis) )
+}
+
+// a type, with each elided (or `'_`) lifetime of a reference named `'i`, i.e., `&str` -> `&'i str`;
+// where a lifetime can't be elided, as in `impl IntoIterator<Item = &'i str>`
+pub(crate) fn name_lifetimes(ty: TokenStream) -> TokenStream {
+    let mut tokens = ty.into_iter().peekable();
+    let mut named = TokenStream::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Group(group) => {
+                let mut inner = Group::new(group.delimiter(), name_lifetimes(group.stream()));
+                inner.set_span(group.span());
+                inner.to_tokens(&mut named);
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '&' => {
+                punct.to_tokens(&mut named);
+                match tokens.peek() {
+                    // `&'_ T`
+                    Some(TokenTree::Punct(tick)) if tick.as_char() == '\'' => {
+                        let tick = tokens.next().unwrap();
+                        match tokens.next() {
+                            Some(TokenTree::Ident(name)) if name == "_" => quote!('i).to_tokens(&mut named),
+                            name => {
+                                tick.to_tokens(&mut named);
+                                name.to_tokens(&mut named);
+                            }
+                        }
+                    }
+                    _ => quote!('i).to_tokens(&mut named),
+                }
+            }
+            token => token.to_tokens(&mut named),
+        }
+    }
+    named
+}
@@ -1,10 +1,21 @@
-use super::common::{file_types, Separator};
+use super::body::Encoding;
+use super::common::{duration, file_types, Separator};
+use super::envelope::Envelope;
+use super::format::Format;
+use super::method::Method;
 use super::node::Node;
-use quote::quote;
+use super::paginate::Paginate;
+use super::params::{check_setters, Param};
+use super::path::Template;
+use super::retry::Retry;
+use proc_macro2::Span;
+use quote::{quote, ToTokens};
 use std::collections::HashMap;
 use syn::{
-    braced, bracketed,
-    parse::{Parse, ParseStream},
+    braced, bracketed, parenthesized,
+    parse::{Parse, ParseStream, Parser},
+    punctuated::Punctuated,
+    spanned::Spanned,
     Expr, Ident, LitStr, Token, Type,
 };
 
@@ -13,6 +24,35 @@ use syn::{
 ///   #[query("/append/this/string"), rename("rename_to_this")]
 ///   "my_endpoint": MyType,
 ///
+///   #[params(id: u32, limit: Option<u32>)]
+///   "paged/endpoint": PagedType,
+///
+///   #[method: DELETE]
+///   "order/{id}": OrderAck,
+///
+///   #[body: form]
+///   "orders": CreateOrder => OrderAck,
+///
+///   #[envelope(data: "observations")]
+///   "series/observations": Vec<Observation>,
+///
+///   "rates.csv": Vec<Rate>,
+///
+///   #[weight: 20]
+///   "api/v3/depth": OrderBook,
+///
+///   #[retry(attempts: 3, non_idempotent: true)]
+///   "orders/new": CreateOrder => OrderAck,
+///
+///   #[timeout: 2s]
+///   "ticker": Ticker,
+///
+///   #[cache: 1d]
+///   "company_tickers.json": Tickers,
+///
+///   #[paginate(offset = "offset", limit = "limit", total = "count", items = "seriess")]
+///   "category/series": CategorySeries,
+///
 ///   "another/endpoint": AnotherType,
 ///   "a/third/endpoint": ThisType,
 /// }
@@ -32,9 +72,10 @@ impl Parse for Dict {
 
             // >> rename attr
             // if rename == "some_value", then retain the original endpoint, but replace the naming convention.
+            // path parameters (`{series_id}`) never make it into the naming convention.
             let fields = match entry.rename {
                 Some(new_name) => new_name,
-                None => entry.endpoint.clone(),
+                None => entry.path.name.clone(),
             };
 
             // split the name into segments, filtering out empties & file types (".json", ".csv", etc.).
@@ -60,25 +101,77 @@ impl Parse for Dict {
                 //
                 // remember: each node will need a `new()` impl, so child nodes will be used in building those TokenStreams, also.
                 if i == last {
-                    let endpoint = entry.endpoint.clone();
-                    let query = entry.query.clone();
-
-                    // since we don't have the `base` url yet, take a segment of the eventual TokenStream (stringified for easy storage).
-                    // if there is Some(query), then append it now, otherwise, just include the original.
-                    //
-                    // also, include the type.
-                    if let Some(query) = query {
-                        node.endpoint = Some(quote! {
-                            let url = format!("{}{}", #endpoint, #query);
+                    // two keys of the same name, i.e., `"orders"` & `"orders/{id}"`, would share a node
+                    if node.is_http() {
+                        return Err(syn::Error::new(
+                            entry.span,
+                            format!(
+                                "`{}` is named as `{}`, another endpoint; `rename` one of them",
+                                entry.endpoint, node.key
+                            ),
+                        ));
+                    }
+
+                    // since we don't have the `base` url yet, take a segment of the eventual TokenStream;
+                    // the query (if any) is kept separately, to be merged with the global query.
+                    node.key = entry.endpoint.clone();
+                    node.endpoint = Some(entry.path.build());
+                    node.query = entry.query.as_ref().map(|query| quote!( #query ));
+
+                    let de_type = entry.de_type.clone();
+                    node.de_type = Some(quote!( #de_type ));
+
+                    // the request body of `post()`, `put()` & `patch()`; untyped, if not given
+                    node.body = entry.body.as_ref().map(|body| quote!( #body ));
+                    node.encoding = entry.encoding;
+
+                    // the response is opened with the entry's envelope (if any), or the API's
+                    node.envelope = entry.envelope.as_ref().map(Envelope::build);
+                    node.format = entry.format;
+                    node.weight = entry.weight;
+                    node.retry = entry.retry.as_ref().map(Retry::build);
+                    node.timeout = entry.timeout;
+                    node.cache = entry.cache;
+                    node.item = entry.paginate.as_ref().map(Paginate::item);
+                    node.paginate = entry.paginate.as_ref().map(Paginate::build);
+
+                    // path parameters become the arguments of the HTTP methods
+                    node.path_params = entry
+                        .path
+                        .params
+                        .iter()
+                        .map(|param| {
+                            let ty = &param.ty;
+                            (param.name.clone(), quote!( #ty ))
                         })
+                        .collect();
+
+                    // only the given HTTP methods are generated (`get()` & `post()` by default)
+                    node.methods = if entry.methods.is_empty() {
+                        Method::defaults()
                     } else {
-                        node.endpoint = Some(quote! {
-                            let url = String::from(#endpoint);
-                        })
+                        entry.methods.clone()
                     };
 
-                    let de_type = entry.de_type.clone();
-                    node.de_type = Some(quote!( #de_type ));
+                    // query parameters become setters of the request builder
+                    node.params = entry.params.iter().map(Param::setter).collect();
+                    node.required = entry
+                        .params
+                        .iter()
+                        .filter(|param| param.is_required())
+                        .map(Param::key)
+                        .collect();
+
+                    // with the path parameters, the required ones are the items of `get_many()`
+                    node.batch = entry
+                        .params
+                        .iter()
+                        .filter(|param| param.is_required())
+                        .map(|param| {
+                            let ty = &param.ty;
+                            (param.setter_name(), quote!( #ty ))
+                        })
+                        .collect();
                 } else {
                     node.children.insert(fields[i + 1].to_string());
                 }
@@ -98,32 +191,67 @@ impl Parse for Dict {
     }
 }
 
-/// Parse a single Record of a Dict - this includes: endpoint, type, queries, and rename.
+/// Parse a single Record of a Dict - this includes: endpoint, type(s), queries, params, methods, body
+/// encoding, response format & envelope, rate-limit weight, retry policy, timeout, cache,
+/// pagination, and rename.
 ///
 /// ```rust
 /// #[query: "/append/this/string", rename: "rename_to_this"]
-/// "my_endpoint": MyType,
+/// #[params(limit: Option<u32>), method(GET, DELETE)]
+/// "my_endpoint/{id: u32}": MyType,
+///
+/// #[body: form]
+/// "orders": CreateOrder => OrderAck,  // `BodyType => ResponseType`
+///
+/// #[format: xml]
+/// "feed": Feed,                       // or by extension, i.e., `"feed.xml"`
+///
+/// #[weight: 20]
+/// "depth": OrderBook,                 // takes 20 of the API's `rate_limit:`; 1 by default
 /// ```
 pub struct Entry {
     pub endpoint: String,
+    pub span: Span, // of the key, for errors
+    pub path: Template,
     pub de_type: Type,
+    pub body: Option<Type>,
+    pub encoding: Encoding,
+    pub envelope: Option<Envelope>,
+    pub format: Format,
+    pub weight: u32,
+    pub retry: Option<Retry>,
+    pub timeout: Option<u64>, // milliseconds
+    pub cache: Option<u64>,   // milliseconds
+    pub paginate: Option<Paginate>,
     pub query: Option<Expr>,
     pub rename: Option<String>,
+    pub params: Vec<Param>,
+    pub methods: Vec<Method>,
 }
 
 impl Parse for Entry {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut query: Option<Expr> = None;
         let mut rename: Option<String> = None;
+        let mut params: Vec<Param> = vec![];
+        let mut methods: Vec<Method> = vec![];
+        let mut encoding = Encoding::default();
+        let mut has_encoding = false; // given with `#[body]`
+        let mut envelope: Option<Envelope> = None;
+        let mut format: Option<Format> = None;
+        let mut weight: u32 = 1;
+        let mut retry: Option<Retry> = None;
+        let mut timeout: Option<u64> = None;
+        let mut cache: Option<u64> = None;
+        let mut paginate: Option<Paginate> = None;
 
         // parse any attributes: `#[ ... ]`
-        // let lookahead = input.lookahead1();
-        if input.peek(Token![#]) {
+        while input.peek(Token![#]) {
             input.parse::<Token![#]>()?;
 
             let attrs;
             bracketed!(attrs in input);
-            let _ = attrs
+            attrs
                 .parse_terminated(Attr::parse, Token![,])?
                 .into_iter()
                 .map(|attr| match attr.fn_id.to_string().as_str() {
@@ -155,25 +283,157 @@ impl Parse for Entry {
                         rename = Some(arg);
                         Ok(())
                     }
-                    _ => {
-                        return Err(syn::Error::new(
-                            input.span(),
-                            "dict macro input not recognised",
-                        ))
+
+                    // `params(name: Type, ...)`
+                    "params" => {
+                        let tokens = match attr.arg {
+                            Expr::Verbatim(tokens) => tokens,
+                            arg => quote!( #arg ),
+                        };
+                        let parsed =
+                            Punctuated::<Param, Token![,]>::parse_terminated.parse2(tokens)?;
+                        params.extend(parsed);
+                        check_setters(&params)
+                    }
+
+                    // `method: DELETE`, or `method(GET, DELETE)`
+                    "method" | "methods" => {
+                        methods.extend(Method::parse_list(attr.arg)?);
+                        Ok(())
+                    }
+
+                    // `body: json`, `body: form`, or `body: bytes`
+                    "body" => {
+                        encoding = Encoding::parse(attr.arg)?;
+                        has_encoding = true;
+                        Ok(())
+                    }
+
+                    // `envelope(data: "field", ...)`
+                    "envelope" => {
+                        let tokens = match attr.arg {
+                            Expr::Verbatim(tokens) => tokens,
+                            arg => quote!( #arg ),
+                        };
+                        envelope = Some(syn::parse2::<Envelope>(tokens)?);
+                        Ok(())
+                    }
+
+                    // `format: csv`; otherwise, by the key's extension
+                    "format" => {
+                        format = Some(Format::parse(attr.arg)?);
+                        Ok(())
+                    }
+
+                    // `weight: 20`; of the API's rate limit
+                    "weight" => {
+                        let lit = syn::parse2::<syn::LitInt>(attr.arg.into_token_stream())?;
+                        weight = lit.base10_parse()?;
+                        Ok(())
+                    }
+
+                    // `retry(attempts: 3, ...)`
+                    "retry" => {
+                        let tokens = match attr.arg {
+                            Expr::Verbatim(tokens) => tokens,
+                            arg => quote!( #arg ),
+                        };
+                        retry = Some(syn::parse2::<Retry>(tokens)?);
+                        Ok(())
+                    }
+
+                    // `timeout: 2s`; of each attempt, over the API's
+                    "timeout" => {
+                        let lit = syn::parse2::<syn::LitInt>(attr.arg.into_token_stream())?;
+                        timeout = Some(duration(&lit)?);
+                        Ok(())
                     }
+
+                    // `cache: 10m`; the ttl of a `GET`, over the API's
+                    "cache" => {
+                        let lit = syn::parse2::<syn::LitInt>(attr.arg.into_token_stream())?;
+                        cache = Some(duration(&lit)?);
+                        Ok(())
+                    }
+
+                    // `paginate(offset = "offset", ...)`, `paginate(cursor = ..)`, or `paginate(link)`
+                    "paginate" => {
+                        let tokens = match attr.arg {
+                            Expr::Verbatim(tokens) => tokens,
+                            arg => quote!( #arg ),
+                        };
+                        paginate = Some(syn::parse2::<Paginate>(tokens)?);
+                        Ok(())
+                    }
+                    _ => Err(syn::Error::new(
+                        attr.fn_id.span(),
+                        "dict macro input not recognised",
+                    )),
                 })
-                .collect::<Vec<_>>();
+                .collect::<syn::Result<Vec<()>>>()?;
         }
 
         // then, parse `"LitStr": Type`
-        let endpoint = input.parse::<LitStr>()?.value();
+        let endpoint = input.parse::<LitStr>()?;
+        let path = Template::parse(&endpoint)?;
+        let format = format.unwrap_or_else(|| Format::from_key(&path.name));
+
+        // envelopes are JSON
+        if envelope.is_some() && format != Format::Json {
+            return Err(syn::Error::new(
+                endpoint.span(),
+                "an envelope can only open a `json` response",
+            ));
+        }
+        // pages are `GET`s
+        if paginate.is_some() && !methods.is_empty() && !methods.contains(&Method::Get) {
+            return Err(syn::Error::new(
+                endpoint.span(),
+                "only an endpoint with `GET` can be paginated",
+            ));
+        }
+        let span = endpoint.span();
+        let endpoint = endpoint.value();
         input.parse::<Separator>()?;
         let de_type = input.parse::<Type>()?;
+
+        // `BodyType => ResponseType`
+        let (body, de_type) = if input.peek(Token![=>]) {
+            input.parse::<Token![=>]>()?;
+            (Some(de_type), input.parse::<Type>()?)
+        } else {
+            (None, de_type)
+        };
+
+        // bodies are sent by `POST`, `PUT` & `PATCH`; of the defaults, `post()`
+        if (body.is_some() || has_encoding)
+            && !methods.is_empty()
+            && !methods.iter().any(Method::has_body)
+        {
+            return Err(syn::Error::new(
+                de_type.span(),
+                "a request body needs a method to send it; one of `POST`, `PUT`, or `PATCH`",
+            ));
+        }
+
         Ok(Self {
             endpoint,
+            span,
+            path,
             de_type,
+            body,
+            encoding,
+            envelope,
+            format,
+            weight,
+            retry,
+            timeout,
+            cache,
+            paginate,
             query,
             rename,
+            params,
+            methods,
         })
     }
 }
@@ -190,6 +450,18 @@ impl Parse for Entry {
 /// Possible attributes:
 ///     - query = format!("?api_key_in_the_url", API_KEY)
 ///     - rename = "new_name"
+///     - params(name: Type, ...)
+///     - method = DELETE, or method(GET, DELETE)
+///     - body = json, form, or bytes
+///     - envelope(data: "field", error: "field")
+///     - format = json, csv, xml, yaml, or toml
+///     - weight = 20
+///     - retry(attempts: 3, backoff: constant(1s), on: [503])
+///     - timeout = 2s
+///     - cache = 10m
+///     - paginate(offset = "offset", limit = "limit", total = "count", items = "seriess")
+///
+/// Arguments in parentheses that aren't an expression are kept as `Expr::Verbatim`.
 pub struct Attr {
     pub fn_id: Ident,
     pub arg: Expr,
@@ -200,6 +472,16 @@ impl Parse for Attr {
         let fn_id = input.parse().map_err(|_| {
             syn::Error::new(input.span(), "expected function identifier in dict attr")
         })?;
+
+        // `#fn_id(#arg)`
+        if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let tokens: proc_macro2::TokenStream = content.parse()?;
+            let arg = syn::parse2::<Expr>(tokens.clone()).unwrap_or(Expr::Verbatim(tokens));
+            return Ok(Attr { fn_id, arg });
+        }
+
         input.parse::<Separator>()?;
         let arg = input.parse()?;
         Ok(Attr { fn_id, arg })
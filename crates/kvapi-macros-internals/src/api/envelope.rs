@@ -0,0 +1,91 @@
+use super::common::Separator;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, Lit, LitStr, Token,
+};
+
+/// The envelope of an API's responses, or of a single Dict entry's.
+///
+/// ```rust
+/// envelope: { data: "result", error: "error" }                // Kraken
+/// envelope: { data: "data", success: "code" == "200000" }     // KuCoin
+///
+/// #[envelope(data: "observations")]                           // FRED
+/// "series/observations": Vec<Observation>,
+/// ```
+///
+/// - `data` is the field holding the endpoint's type; `/` separates nested fields
+/// - `error` fails the request if the field is set, and not empty
+/// - `success` fails the request unless the field has the value
+///
+/// An entry's envelope replaces the API's.
+pub struct Envelope {
+    pub data: LitStr,
+    pub error: Option<LitStr>,
+    pub success: Option<(LitStr, Lit)>,
+}
+
+impl Parse for Envelope {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut data: Option<LitStr> = None;
+        let mut error: Option<LitStr> = None;
+        let mut success: Option<(LitStr, Lit)> = None;
+
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Separator>()?;
+            match key.to_string().as_str() {
+                "data" => data = Some(input.parse()?),
+                "error" => error = Some(input.parse()?),
+
+                // `"field" == value`
+                "success" => {
+                    let field: LitStr = input.parse()?;
+                    input.parse::<Token![==]>()?;
+                    success = Some((field, input.parse()?));
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown envelope input; expected one of `data`, `error`, or `success`",
+                    ))
+                }
+            }
+            input.parse::<Option<Token![,]>>()?;
+        }
+
+        let Some(data) = data else {
+            return Err(syn::Error::new(
+                input.span(),
+                "envelope requires `data`, the field holding the response",
+            ));
+        };
+        if error.is_some() && success.is_some() {
+            return Err(syn::Error::new(
+                input.span(),
+                "envelope takes one of `error` or `success`, not both",
+            ));
+        }
+
+        Ok(Self {
+            data,
+            error,
+            success,
+        })
+    }
+}
+
+impl Envelope {
+    // e.g., `kvapi::Envelope::new("result").error("error")`
+    pub(crate) fn build(&self) -> TokenStream {
+        let data = &self.data;
+        let check = match (&self.error, &self.success) {
+            (Some(field), _) => quote!( .error(#field) ),
+            (_, Some((field, value))) => quote!( .success(#field, #value) ),
+            _ => quote!(),
+        };
+        quote!( kvapi::Envelope::new(#data) #check )
+    }
+}
@@ -0,0 +1,71 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Expr, Ident};
+
+/// Format of a Dict entry's response, picked by the extension of its key, or set with the
+/// `format` attribute.
+///
+/// ```rust
+/// "rates.csv": Vec<Rate>,             // csv, by its extension
+///
+/// #[format: xml]
+/// "feed": Feed,
+///
+/// "series": Series,                   // json, by default
+/// ```
+///
+/// Each format, besides `json`, requires the `kvapi` feature of the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Json,
+    Csv,
+    Xml,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// Parse one of `json`, `csv`, `xml`, `yaml`, or `toml`.
+    pub fn parse(arg: Expr) -> syn::Result<Self> {
+        let ident = syn::parse2::<Ident>(arg.into_token_stream())?;
+        Self::from_name(&ident.to_string()).ok_or_else(|| {
+            syn::Error::new(
+                ident.span(),
+                "unknown format; expected one of `json`, `csv`, `xml`, `yaml`, or `toml`",
+            )
+        })
+    }
+
+    /// Format of the extension of the key's last segment, i.e., `"rates.csv"`, before any query;
+    /// `json` otherwise.
+    pub fn from_key(key: &str) -> Self {
+        let path = key.split_once('?').map_or(key, |(path, _)| path);
+        let last = path.rsplit('/').next().unwrap_or_default();
+        last.rsplit_once('.')
+            .and_then(|(_, extension)| Self::from_name(extension))
+            .unwrap_or_default()
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            "xml" => Some(Self::Xml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    // e.g., `kvapi::Format::Csv`
+    pub(crate) fn build(&self) -> TokenStream {
+        match self {
+            Self::Json => quote!(kvapi::Format::Json),
+            Self::Csv => quote!(kvapi::Format::Csv),
+            Self::Xml => quote!(kvapi::Format::Xml),
+            Self::Yaml => quote!(kvapi::Format::Yaml),
+            Self::Toml => quote!(kvapi::Format::Toml),
+        }
+    }
+}
@@ -46,16 +46,19 @@ impl Parse for Headers {
                 let value = parse_str::<Expr>(&header.value).expect("expected Header");
 
                 // match the header to the correct TokenStream
-                if header.is_query == true {
+                if header.is_query {
                     query_headers.push(quote! {
                         // url query
                         .header(#key, #value)
                     });
                 } else {
+                    // evaluated once, when the API is built; any error names the header (& `api`)
                     client_headers.push(quote! {
-                        // client query
-                        let value = #value;
-                        headers.insert(#key, kvapi::HeaderValue::from_str(value).unwrap());
+                        let value = (|| -> std::result::Result<kvapi::HeaderValue, Box<dyn std::error::Error + Send + Sync>> {
+                            Ok(kvapi::HeaderValue::from_str(#value)?)
+                        })()
+                        .map_err(|source| kvapi::Error::Header { api, name: #key, source })?;
+                        headers.insert(#key, value);
                     });
                 }
             })
@@ -99,7 +102,14 @@ impl Parse for Header {
         }
 
         // header entry
-        let key: String = input.parse::<LitStr>()?.value();
+        let key = input.parse::<LitStr>()?;
+        if !is_header_name(&key.value()) {
+            return Err(syn::Error::new(
+                key.span(),
+                "invalid header name; expected letters, digits, or any of: !#$%&'*+-.^_`|~",
+            ));
+        }
+        let key = key.value();
         input.parse::<Separator>()?;
         let value: Expr = input.parse()?;
         let value = quote!( #value ).to_string();
@@ -112,6 +122,14 @@ impl Parse for Header {
     }
 }
 
+// a valid HTTP header name (RFC 9110 `token`)
+pub(crate) fn is_header_name(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
 /// #[per_query]
 /// "Custom-Header": my_function(self.url)
 ///
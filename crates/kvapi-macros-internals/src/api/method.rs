@@ -0,0 +1,88 @@
+use proc_macro2::{Delimiter, Group, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse::Parser, punctuated::Punctuated, Expr, Ident, Token};
+
+/// HTTP method of a Dict entry, set with the `method` attribute.
+///
+/// ```rust
+/// #[method: DELETE]
+/// "order": OrderAck,
+///
+/// #[method(GET, PUT, DELETE)]
+/// "settings": Settings,
+/// ```
+///
+/// Only the listed methods are generated, i.e., `api.order.get()` would not compile above;
+/// without the attribute, an entry gets `get()` & `post()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+    Head,
+}
+
+impl Method {
+    /// `get()` & `post()`, when no methods are given.
+    pub fn defaults() -> Vec<Self> {
+        vec![Self::Get, Self::Post]
+    }
+
+    /// Parse one method (`DELETE`), or a list of them (`[GET, DELETE]`, `(GET, DELETE)`).
+    pub fn parse_list(arg: Expr) -> syn::Result<Vec<Self>> {
+        // unwrap a list from its `[ ]` or `( )`
+        let mut tokens = arg.into_token_stream();
+        if let Ok(group) = syn::parse2::<Group>(tokens.clone()) {
+            if group.delimiter() != Delimiter::Brace {
+                tokens = group.stream();
+            }
+        }
+        let idents = Punctuated::<Ident, Token![,]>::parse_terminated.parse2(tokens)?;
+
+        let mut methods: Vec<Self> = vec![];
+        for ident in idents {
+            let method = match ident.to_string().to_uppercase().as_str() {
+                "GET" => Self::Get,
+                "POST" => Self::Post,
+                "PUT" => Self::Put,
+                "PATCH" => Self::Patch,
+                "DELETE" => Self::Delete,
+                "HEAD" => Self::Head,
+                _ => return Err(syn::Error::new(
+                    ident.span(),
+                    "unknown HTTP method; expected one of GET, POST, PUT, PATCH, DELETE, or HEAD",
+                )),
+            };
+            if !methods.contains(&method) {
+                methods.push(method);
+            }
+        }
+        Ok(methods)
+    }
+
+    // name of the generated function, e.g., `delete`
+    pub(crate) fn name(&self) -> Ident {
+        let name = match self {
+            Self::Get => "get",
+            Self::Post => "post",
+            Self::Put => "put",
+            Self::Patch => "patch",
+            Self::Delete => "delete",
+            Self::Head => "head",
+        };
+        format_ident!("{}", name)
+    }
+
+    // e.g., `kvapi::Method::DELETE`
+    pub(crate) fn http(&self) -> TokenStream {
+        let method = format_ident!("{}", self.name().to_string().to_uppercase());
+        quote!(kvapi::Method::#method)
+    }
+
+    // methods that send a request body
+    pub(crate) fn has_body(&self) -> bool {
+        matches!(self, Self::Post | Self::Put | Self::Patch)
+    }
+}
@@ -1,6 +1,18 @@
+pub mod auth;
+pub mod body;
+pub mod cache;
 pub mod builder;
 pub mod common;
 pub mod dict;
+pub mod envelope;
+pub mod format;
 // pub mod director;
 pub mod headers;
+pub mod method;
 pub mod node;
+pub mod paginate;
+pub mod params;
+pub mod path;
+pub mod rate_limit;
+pub mod retry;
+pub mod ws;
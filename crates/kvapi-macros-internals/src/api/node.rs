@@ -1,9 +1,15 @@
-use super::headers::Headers;
+use super::{
+    body::Encoding,
+    common::{build_duration, name_lifetimes},
+    format::Format,
+    headers::Headers,
+    method::Method,
+};
 use convert_case::{Case, Casing};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use std::collections::HashSet;
-use syn::Ident;
+use syn::{Expr, Ident};
 
 /// Nodes are the segments of the key in `"key": EndpointType` in a Dict Entry
 ///
@@ -18,6 +24,9 @@ use syn::Ident;
 ///                         async fn get() -> Result<MyType> { ... }
 ///                    }
 ///
+/// Path parameters (`"path/{id: u32}/endpoint"`) are not nodes; they become the
+/// arguments of the leaf's HTTP methods instead, i.e., `get(id: u32)`.
+///
 /// The struct build of this would result in:
 /// `SomeApiName.path.to.endpoint.get()`
 ///
@@ -28,8 +37,36 @@ pub struct Node {
     pub root: bool,                   // add to the Root fields
     pub de_type: Option<TokenStream>, // type of the `get()` result; if none, no `get()` needed
     pub children: HashSet<String>,    // determines `new()` tokens
-    pub endpoint: Option<TokenStream>, // if leaf node, remember the original endpoint for `url()`
-                                      // (and any additional query)
+    // `(name, Type)` of each path parameter; the arguments of the HTTP methods
+    pub path_params: Vec<(Ident, TokenStream)>,
+    pub params: Vec<TokenStream>, // setters of the request builder, one per query parameter
+    pub required: Vec<String>,    // keys of the query parameters that must be set
+    // `(setter, Type)` of each required query parameter; with the path parameters, the item
+    // of `get_many()`
+    pub batch: Vec<(Ident, TokenStream)>,
+    pub methods: Vec<Method>,     // HTTP methods to generate, i.e., `get()`, `delete()`
+    pub body: Option<TokenStream>, // type of the request body; if none, any `kvapi::Value`
+    pub encoding: Encoding,       // how the request body is sent
+    // the envelope of the response, if not the API's
+    pub envelope: Option<TokenStream>,
+    // the format of the response; only `json` is opened with an envelope
+    pub format: Format,
+    // tokens taken from the API's rate limit, per request
+    pub weight: u32,
+    // the retry policy of the endpoint, if not the API's
+    pub retry: Option<TokenStream>,
+    // the timeout of each attempt, in milliseconds, if not the API's
+    pub timeout: Option<u64>,
+    // the ttl of a cached `GET`, in milliseconds, if not the API's
+    pub cache: Option<u64>,
+    // how the pages of a `GET` follow one another, and the type of their items, if paginated
+    pub paginate: Option<TokenStream>,
+    pub item: Option<TokenStream>,
+    // if leaf node, remember the dict key, as written, to name the endpoint in errors
+    pub key: String,
+    // if leaf node, remember the original endpoint (and any additional query) for `url()`
+    pub endpoint: Option<TokenStream>,
+    pub query: Option<TokenStream>,
 }
 
 impl Node {
@@ -39,7 +76,24 @@ impl Node {
             root: false,
             de_type: None,
             children: HashSet::new(),
+            key: String::new(),
             endpoint: None,
+            query: None,
+            path_params: vec![],
+            params: vec![],
+            required: vec![],
+            batch: vec![],
+            methods: vec![],
+            body: None,
+            encoding: Encoding::default(),
+            envelope: None,
+            format: Format::default(),
+            weight: 1,
+            retry: None,
+            timeout: None,
+            cache: None,
+            paginate: None,
+            item: None,
         }
     }
 
@@ -59,90 +113,475 @@ impl Node {
 
     // check if the node is an HTTP node
     pub(crate) fn is_http(&self) -> bool {
-        if self.de_type.is_some() {
-            true
-        } else {
-            false
-        }
+        self.de_type.is_some()
     }
 
-    // build the url, combining with a base URl, if there is one
-    pub(crate) fn build_url(&self, base: Option<TokenStream>) -> TokenStream {
+    // build the url, combining with a base URl & the global query, if there are any;
+    // the query levels are merged in order: global, endpoint, `#[query]` (then per-call params)
+    pub(crate) fn build_url(&self, base: Option<TokenStream>, query: Option<Expr>) -> TokenStream {
         let url = self.endpoint.as_ref().unwrap();
-        if let Some(base) = base {
-            quote! {
-                #url
-                let base = #base;
-                let url = format!("{}{}", base, url);
-            }
-        } else {
-            url.clone()
+        let base = base.map(|base| quote!( .base(#base) ));
+        let global = query.map(|query| quote!( .query(#query) ));
+        let entry = self.query.as_ref().map(|query| quote!( .query(#query) ));
+        quote! {
+            #url
+            kvapi::url::UrlBuilder::new()
+                #base
+                #global
+                .path(&url)
+                #entry
         }
     }
 
     // build the HTTP functions
-    pub(crate) fn build_http(&self, url: TokenStream, headers: Option<Headers>) -> TokenStream {
-        let de_type = self.de_type.clone().unwrap();
-        let headers = headers.unwrap_or(Headers {
-            client: vec![],
-            query: vec![],
+    pub(crate) fn build_http(&self, url: TokenStream, request: &Ident) -> TokenStream {
+        // path parameters, e.g., `get(&self, series_id: &str)`
+        let names: Vec<&Ident> = self.path_params.iter().map(|(name, _)| name).collect();
+        let args: Vec<TokenStream> = self
+            .path_params
+            .iter()
+            .map(|(name, ty)| quote! { #name: #ty })
+            .collect();
+        let required = &self.required;
+        let body = self.body_type();
+        let timeout = match self.timeout {
+            Some(timeout) => {
+                let timeout = build_duration(timeout);
+                quote!( Some(#timeout) )
+            }
+            None => quote!(None),
+        };
+
+        // each HTTP method is a shortcut to the request builder's, e.g., `delete(&self, id: u32)`
+        let methods = self.methods.iter().map(|method| {
+            let name = method.name();
+            let output = self.output(method);
+            if method.has_body() {
+                quote! {
+                    pub async fn #name(&self, #( #args, )* body: &#body) -> kvapi::Result<#output> {
+                        self.request(#( #names ),*).#name(body).await
+                    }
+                }
+            } else {
+                quote! {
+                    pub async fn #name(&self, #( #args ),*) -> kvapi::Result<#output> {
+                        self.request(#( #names ),*).#name().await
+                    }
+                }
+            }
         });
-        let client_headers = headers.client;
-        let query_headers = headers.query;
 
-        let http_methods = quote! {
-            fn build_client() -> kvapi::Result<kvapi::Client> {
-                let mut headers = kvapi::HeaderMap::new();
-                #( #client_headers )*
-                let client = kvapi::ClientBuilder::new()
-                    .default_headers(headers)
-                    .build()?;
-                Ok(client)
+        // variants of `get()`, i.e., `get_text()`, `get_as::<U>()`, or `get_with_meta()`
+        let variants = self.get_variants().into_iter().map(|variant| {
+            let (name, generics) = (variant.name(), variant.generics());
+            let output = variant.output(self.de_type.as_ref().unwrap());
+            quote! {
+                pub async fn #name #generics(&self, #( #args ),*) -> kvapi::Result<#output> {
+                    self.request(#( #names ),*).#name().await
+                }
             }
+        });
 
-            fn build_url() -> String {
-                #url
-                url
+        // `pages()` & `items()` of a paginated endpoint
+        let pages = self.paginated().map(|(de_type, item)| {
+            quote! {
+                /// Every page of this endpoint, from the first; one request each, as it's read.
+                pub fn pages(&self, #( #args ),*) -> impl kvapi::Stream<Item = kvapi::Result<#de_type>> + '_ {
+                    self.request(#( #names ),*).pages()
+                }
+
+                /// The items of every page of this endpoint, in order.
+                pub fn items(&self, #( #args ),*) -> impl kvapi::Stream<Item = kvapi::Result<#item>> + '_ {
+                    self.request(#( #names ),*).items()
+                }
             }
+        });
+
+        // `get_many()` of a `GET`, by its path & required query parameters
+        let many = self.batched().map(|(pattern, item)| {
+            let de_type = self.de_type.as_ref().unwrap();
+            let (key, format, envelope) = (&self.key, self.format.build(), self.owned_envelope());
+            let setters = self.batch.iter().map(|(setter, _)| setter);
+            quote! {
+                /// Get this endpoint once per item, by its path & required query parameters, a
+                /// few at a time; see [`kvapi::Many`].
+                pub fn get_many<'i>(&self, items: impl IntoIterator<Item = #item>) -> kvapi::Many<'_, #de_type> {
+                    let requests = items
+                        .into_iter()
+                        .map(|#pattern| self.request(#( #names ),*) #( .#setters(#setters) )* .get_call())
+                        .collect();
+                    self.api.many(#key, #format, #envelope, requests)
+                }
+            }
+        });
 
-            fn url(&self) -> &String {
-                &self.url
+        // `watch()` of a `GET`
+        let watch = self.methods.contains(&Method::Get).then(|| {
+            let de_type = self.de_type.as_ref().unwrap();
+            quote! {
+                /// Get this endpoint every `interval`, as a `Stream` of its decoded responses.
+                pub fn watch(&self, #( #args, )* interval: std::time::Duration) -> kvapi::Watch<'_, #de_type> {
+                    self.request(#( #names ),*).watch(interval)
+                }
+            }
+        });
+
+        let http_methods = quote! {
+            fn url(&self, #( #args ),*) -> kvapi::url::UrlBuilder {
+                #url
             }
 
             fn client(&self) -> &kvapi::Client {
-                &self.client
-            }
-
-            pub async fn get(&self) -> kvapi::Result<#de_type> {
-                let response: #de_type = self
-                    .client()
-                    .get(self.url())
-                    #( #query_headers )*
-                    .send()
-                    .await?
-                    .json()
-                    .await?;
-                Ok(response)
-            }
-
-            pub async fn post(&self, json: kvapi::Value) -> kvapi::Result<#de_type> {
-                let response: #de_type = self
-                    .client()
-                    .post(self.url())
-                    .json(&json)
-                    .send()
-                    .await?
-                    .json()
-                    .await?;
-                Ok(response)
+                self.api.client()
             }
+
+            /// Print the URL of this endpoint.
+            pub fn dbg_url(&self, #( #args ),*) {
+                match self.url(#( #names ),*).build() {
+                    Ok(url) => println!("{}", url),
+                    Err(e) => println!("{:#}", e),
+                }
+            }
+
+            /// Print the HTTP client of this endpoint.
+            pub fn dbg_client(&self) {
+                println!("{:#?}", self.client());
+            }
+
+            /// Build a request, setting its query parameters one at a time.
+            pub fn request(&self, #( #args ),*) -> #request<'_> {
+                #request {
+                    endpoint: self,
+                    url: self.url(#( #names ),*),
+                    params: kvapi::query::Params::new(&[#( #required ),*]),
+                    timeout: #timeout,
+                }
+            }
+
+            #( #methods )*
+            #( #variants )*
+            #many
+            #watch
+            #pages
         };
 
         http_methods
     }
 
+    // build the request builder of a leaf node; one setter per query parameter
+    pub(crate) fn build_request(
+        &self,
+        pascal: &Ident,
+        request: &Ident,
+        headers: Option<Headers>,
+    ) -> TokenStream {
+        let query_headers = headers.map(|headers| headers.query).unwrap_or_default();
+        let setters = &self.params;
+        let body = self.body_type();
+        let encode = self.encoding.apply();
+
+        let key = &self.key;
+        let weight = self.weight;
+        let retry = self.retry.as_ref().map(|retry| quote!( .retry(#retry) ));
+        let cache = self.cache.map(|ttl| {
+            let ttl = build_duration(ttl);
+            quote!( .cache(#ttl) )
+        });
+        let format = self.format.build();
+        let envelope = match &self.envelope {
+            Some(envelope) => quote!( Some(&#envelope) ),
+            None => quote!(None),
+        };
+
+        // the envelope of a stream, kept for each of its requests
+        let owned_envelope = self.owned_envelope();
+
+        // the pages, from the one of this request
+        let pages = self.paginated().map(|(de_type, item)| {
+            let paginate = self.paginate.as_ref().unwrap();
+            let envelope = &owned_envelope;
+            quote! {
+                /// Every page of this endpoint, from the one of this request; one request each,
+                /// as it's read.
+                pub fn pages(self) -> impl kvapi::Stream<Item = kvapi::Result<#de_type>> + 'a {
+                    let (endpoint, request) = (self.endpoint, self.get_call());
+                    endpoint.api.pages(#key, #format, #envelope, #paginate, request)
+                }
+
+                /// The items of every page, in order.
+                pub fn items(self) -> impl kvapi::Stream<Item = kvapi::Result<#item>> + 'a {
+                    let (endpoint, request) = (self.endpoint, self.get_call());
+                    endpoint.api.items(#key, #format, #envelope, #paginate, request)
+                }
+            }
+        });
+
+        // this request, as a `GET` call; once per item of `get_many()`
+        let get_call = self.methods.contains(&Method::Get).then(|| {
+            quote! {
+                fn get_call(&self) -> kvapi::Result<kvapi::Call> {
+                    self.build(kvapi::Method::GET).map(|request| self.call(request))
+                }
+            }
+        });
+        let many = (self.methods.contains(&Method::Get) && !self.batch.is_empty()).then(|| {
+            let de_type = self.de_type.as_ref().unwrap();
+            let setters: Vec<&Ident> = self.batch.iter().map(|(setter, _)| setter).collect();
+            let types = self.batch.iter().map(|(_, ty)| ty);
+            let (pattern, item) = match &setters[..] {
+                [setter] => (quote!(#setter), types.clone().next().unwrap().clone()),
+                _ => (quote!( (#( #setters ),*) ), quote!( (#( #types ),*) )),
+            };
+            let item = name_lifetimes(item);
+            quote! {
+                /// This request, once per item, by its required query parameters, a few at a
+                /// time; see [`kvapi::Many`].
+                pub fn get_many<'i>(self, items: impl IntoIterator<Item = #item>) -> kvapi::Many<'a, #de_type> {
+                    let requests = items
+                        .into_iter()
+                        .map(|#pattern| {
+                            let request = #request {
+                                endpoint: self.endpoint,
+                                url: self.url.clone(),
+                                params: self.params.clone(),
+                                timeout: self.timeout,
+                            };
+                            request #( .#setters(#setters) )* .get_call()
+                        })
+                        .collect();
+                    let endpoint = self.endpoint;
+                    endpoint.api.many(#key, #format, #owned_envelope, requests)
+                }
+            }
+        });
+
+        // this request, polled
+        let watch = self.methods.contains(&Method::Get).then(|| {
+            let de_type = self.de_type.as_ref().unwrap();
+            quote! {
+                /// Send this request every `interval`, as a `Stream` of its decoded responses;
+                /// see [`kvapi::Watch`] to only yield changes, skip errors, or end it.
+                pub fn watch(self, interval: std::time::Duration) -> kvapi::Watch<'a, #de_type> {
+                    let (endpoint, request) = (self.endpoint, self.get_call());
+                    endpoint.api.watch(#key, #format, #owned_envelope, request, interval)
+                }
+            }
+        });
+
+        let methods = self.methods.iter().map(|method| {
+            let name = method.name();
+            let http = method.http();
+            let output = self.output(method);
+            if method == &Method::Head {
+                quote! {
+                    pub async fn #name(&self) -> kvapi::Result<#output> {
+                        let response = self.api().send(self.call(self.build(#http)?)).await?;
+                        Ok(response.headers().clone())
+                    }
+                }
+            } else if method.has_body() {
+                quote! {
+                    pub async fn #name(&self, body: &#body) -> kvapi::Result<#output> {
+                        let request = self.call(self.build(#http)? #encode);
+                        self.api().decode(#key, #format, #envelope, request).await
+                    }
+                }
+            } else {
+                quote! {
+                    pub async fn #name(&self) -> kvapi::Result<#output> {
+                        let request = self.call(self.build(#http)?);
+                        self.api().decode(#key, #format, #envelope, request).await
+                    }
+                }
+            }
+        });
+
+        let variants = self.get_variants().into_iter().map(|variant| {
+            let (name, generics) = (variant.name(), variant.generics());
+            let output = variant.output(self.de_type.as_ref().unwrap());
+            let send = match variant {
+                GetVariant::Bytes => quote!(self.api().bytes(request)),
+                GetVariant::Text => quote!(self.api().text(request)),
+                GetVariant::As => quote!( self.api().decode(#key, #format, #envelope, request) ),
+                GetVariant::Response => quote!(self.api().execute(request)),
+                GetVariant::WithMeta => {
+                    quote!( self.api().decode_with_meta(#key, #format, #envelope, request) )
+                }
+            };
+            quote! {
+                pub async fn #name #generics(&self) -> kvapi::Result<#output> {
+                    let request = self.call(self.build(kvapi::Method::GET)?);
+                    #send.await
+                }
+            }
+        });
+
+        quote! {
+            #[doc = concat!("Request builder of [`", stringify!(#pascal), "`].")]
+            pub struct #request<'a> {
+                endpoint: &'a #pascal,
+                url: kvapi::url::UrlBuilder,
+                params: kvapi::query::Params,
+                timeout: Option<std::time::Duration>,
+            }
+
+            impl<'a> #request<'a> {
+                #( #setters )*
+
+                fn url(&self) -> kvapi::Result<kvapi::url::Url> {
+                    self.url.clone().query(self.params.partial()).build()
+                }
+
+                fn client(&self) -> &kvapi::Client {
+                    self.endpoint.client()
+                }
+
+                fn api(&self) -> &kvapi::Api {
+                    &self.endpoint.api
+                }
+
+                // the request, with its url, the API's headers & any per-request headers
+                fn build(&self, method: kvapi::Method) -> kvapi::Result<kvapi::RequestBuilder> {
+                    self.params.query()?;
+                    let request = self
+                        .client()
+                        .request(method, self.url()?)
+                        .headers(self.api().headers().clone())
+                        #( #query_headers )*;
+                    Ok(request)
+                }
+
+                /// Time out each attempt of this request after `timeout`, rather than the
+                /// endpoint's, or the API's.
+                pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+                    self.timeout = Some(timeout);
+                    self
+                }
+
+                // the request, with the endpoint's weight of the rate limit, retry policy,
+                // timeout & cache ttl
+                fn call(&self, request: kvapi::RequestBuilder) -> kvapi::Call {
+                    let call = kvapi::Call::new(request).weight(#weight) #retry #cache;
+                    match self.timeout {
+                        Some(timeout) => call.timeout(timeout),
+                        None => call,
+                    }
+                }
+
+                #( #methods )*
+                #( #variants )*
+                #get_call
+                #many
+                #watch
+                #pages
+            }
+        }
+    }
+
+    // the envelope, if not the API's, as kept by a stream of requests
+    fn owned_envelope(&self) -> TokenStream {
+        match &self.envelope {
+            Some(envelope) => quote!( Some(#envelope) ),
+            None => quote!(None),
+        }
+    }
+
+    // `(pattern, Type)` of the items of `get_many()`; a tuple of the path & required query
+    // parameters, unless there's one
+    fn batched(&self) -> Option<(TokenStream, TokenStream)> {
+        if !self.methods.contains(&Method::Get) {
+            return None;
+        }
+        let params = self.path_params.iter().chain(&self.batch);
+        let (names, types): (Vec<&Ident>, Vec<&TokenStream>) =
+            params.map(|(name, ty)| (name, ty)).unzip();
+        let (pattern, item) = match (&names[..], &types[..]) {
+            ([], _) => return None,
+            ([name], [ty]) => (quote!(#name), quote!(#ty)),
+            _ => (quote!( (#( #names ),*) ), quote!( (#( #types ),*) )),
+        };
+        Some((pattern, name_lifetimes(item)))
+    }
+
+    // `(page, item)` types of a paginated `GET`
+    fn paginated(&self) -> Option<(TokenStream, TokenStream)> {
+        if self.paginate.is_none() || !self.methods.contains(&Method::Get) {
+            return None;
+        }
+        Some((self.de_type.clone()?, self.item.clone()?))
+    }
+
+    // the variants of `get()`, if the entry has one
+    fn get_variants(&self) -> Vec<GetVariant> {
+        if !self.methods.contains(&Method::Get) {
+            return vec![];
+        }
+        GetVariant::ALL.to_vec()
+    }
+
+    // output of an HTTP method; `head()` only has headers
+    fn output(&self, method: &Method) -> TokenStream {
+        match method {
+            Method::Head => quote!(kvapi::HeaderMap),
+            _ => self.de_type.clone().unwrap(),
+        }
+    }
+
+    // type of the request body; `kvapi::Value` when the entry doesn't declare one
+    fn body_type(&self) -> TokenStream {
+        self.body.clone().unwrap_or(quote!(kvapi::Value))
+    }
+
     // check if the node is a root node
     pub(crate) fn is_root(&self) -> bool {
         self.root
     }
 }
+
+// a variant of `get()`: the body as bytes or text, decoded as another type, the response as is
+// (any status), or the decoded body with the status, headers & timing of the response
+#[derive(Clone, Copy)]
+enum GetVariant {
+    Bytes,
+    Text,
+    As,
+    Response,
+    WithMeta,
+}
+
+impl GetVariant {
+    const ALL: [Self; 5] = [
+        Self::Bytes,
+        Self::Text,
+        Self::As,
+        Self::Response,
+        Self::WithMeta,
+    ];
+
+    fn name(self) -> Ident {
+        match self {
+            Self::Bytes => format_ident!("get_bytes"),
+            Self::Text => format_ident!("get_text"),
+            Self::As => format_ident!("get_as"),
+            Self::Response => format_ident!("get_response"),
+            Self::WithMeta => format_ident!("get_with_meta"),
+        }
+    }
+
+    fn generics(self) -> TokenStream {
+        match self {
+            Self::As => quote!( <U: kvapi::DeserializeOwned> ),
+            Self::Bytes | Self::Text | Self::Response | Self::WithMeta => quote!(),
+        }
+    }
+
+    fn output(self, de_type: &TokenStream) -> TokenStream {
+        match self {
+            Self::Bytes => quote!(kvapi::Bytes),
+            Self::Text => quote!(String),
+            Self::As => quote!(U),
+            Self::Response => quote!(kvapi::reqwest::Response),
+            Self::WithMeta => quote!(kvapi::Response<#de_type>),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use super::common::Separator;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, LitInt, LitStr, Token, Type,
+};
+
+/// The pagination of a Dict entry; by offset, cursor, or `Link` header.
+///
+/// ```rust
+/// #[paginate(offset = "offset", limit = "limit", total = "count", items = "seriess")]
+/// "category/series": CategorySeries,
+///
+/// #[paginate(cursor = "cursor", next = "meta/next_cursor", items = "data", item = Trade)]
+/// "trades": Trades,
+///
+/// #[paginate(link)]
+/// "history": Vec<Trade>,
+/// ```
+///
+/// - `offset` pages may set a page `size`, sent as the `limit`, and stop at the `total`
+/// - `cursor` pages take the `next` cursor from each page; it's required
+/// - `items` is the field of each page's list of items; the page itself, if not given
+/// - `item` is the type of `items()`; `kvapi::Value`, if not given
+pub struct Paginate {
+    pub by: By,
+    pub items: Option<String>,
+    pub item: Option<Type>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum By {
+    Offset {
+        offset: String,
+        limit: Option<String>,
+        size: Option<u64>,
+        total: Option<String>,
+    },
+    Cursor {
+        cursor: String,
+        next: String,
+    },
+    Link,
+}
+
+impl Parse for Paginate {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut offset: Option<String> = None;
+        let mut limit: Option<String> = None;
+        let mut size: Option<u64> = None;
+        let mut total: Option<String> = None;
+        let mut cursor: Option<String> = None;
+        let mut next: Option<String> = None;
+        let mut link = false;
+        let mut items: Option<String> = None;
+        let mut item: Option<Type> = None;
+
+        let span = input.span();
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            match key.to_string().as_str() {
+                // a flag, without a value
+                "link" => link = true,
+                _ => {
+                    input.parse::<Separator>()?;
+                    match key.to_string().as_str() {
+                        "offset" => offset = Some(input.parse::<LitStr>()?.value()),
+                        "limit" => limit = Some(input.parse::<LitStr>()?.value()),
+                        "size" => size = Some(input.parse::<LitInt>()?.base10_parse()?),
+                        "total" => total = Some(input.parse::<LitStr>()?.value()),
+                        "cursor" => cursor = Some(input.parse::<LitStr>()?.value()),
+                        "next" => next = Some(input.parse::<LitStr>()?.value()),
+                        "items" => items = Some(input.parse::<LitStr>()?.value()),
+                        "item" => item = Some(input.parse()?),
+                        _ => {
+                            return Err(syn::Error::new(
+                                key.span(),
+                                "unknown paginate input; expected one of `offset`, `limit`, \
+                                 `size`, `total`, `cursor`, `next`, `link`, `items`, or `item`",
+                            ))
+                        }
+                    }
+                }
+            }
+            input.parse::<Option<Token![,]>>()?;
+        }
+
+        if offset.is_none() && (limit.is_some() || size.is_some() || total.is_some()) {
+            return Err(syn::Error::new(
+                span,
+                "`limit`, `size` & `total` are only of `offset` pages",
+            ));
+        }
+        if size.is_some() && limit.is_none() {
+            return Err(syn::Error::new(
+                span,
+                "a page `size` is sent as the `limit`, which is missing",
+            ));
+        }
+
+        let by = match (offset, cursor, link) {
+            (Some(offset), None, false) => By::Offset {
+                offset,
+                limit,
+                size,
+                total,
+            },
+            (None, Some(cursor), false) => {
+                let Some(next) = next else {
+                    return Err(syn::Error::new(
+                        span,
+                        "cursor pages need the field of the `next` cursor",
+                    ));
+                };
+                By::Cursor { cursor, next }
+            }
+            (None, None, true) => By::Link,
+            _ => {
+                return Err(syn::Error::new(
+                    span,
+                    "expected exactly one of `offset`, `cursor`, or `link`",
+                ))
+            }
+        };
+        Ok(Self { by, items, item })
+    }
+}
+
+impl Paginate {
+    // e.g., `kvapi::Paginate::offset("offset").limit("limit").total("count").items("seriess")`
+    pub(crate) fn build(&self) -> TokenStream {
+        let by = match &self.by {
+            By::Offset {
+                offset,
+                limit,
+                size,
+                total,
+            } => {
+                let limit = limit.as_ref().map(|limit| quote!( .limit(#limit) ));
+                let size = size.map(|size| quote!( .size(#size) ));
+                let total = total.as_ref().map(|total| quote!( .total(#total) ));
+                quote!( kvapi::Paginate::offset(#offset) #limit #size #total )
+            }
+            By::Cursor { cursor, next } => quote!( kvapi::Paginate::cursor(#cursor, #next) ),
+            By::Link => quote!(kvapi::Paginate::link()),
+        };
+        let items = self.items.as_ref().map(|items| quote!( .items(#items) ));
+        quote!( #by #items )
+    }
+
+    // the type of `items()`
+    pub(crate) fn item(&self) -> TokenStream {
+        match &self.item {
+            Some(item) => quote!( #item ),
+            None => quote!(kvapi::Value),
+        }
+    }
+}
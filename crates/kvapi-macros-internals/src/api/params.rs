@@ -0,0 +1,172 @@
+use convert_case::{Case, Casing};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    GenericArgument, Ident, PathArguments, Token, Type,
+};
+
+/// A query parameter, declared on a Dict entry with `#[params(...)]`.
+///
+/// ```rust
+/// #[params(series_id: &str, limit: Option<u32>, symbols: Option<Vec<&str>> as json)]
+/// "series/observations": Observations,
+/// ```
+///
+/// Each parameter becomes a setter on the endpoint's request builder, i.e.,
+/// `api.series.observations.request().series_id("DGS10").limit(100).get()`.
+///
+/// - `Option<T>` parameters are optional, and left out of the query when unset
+/// - any other parameter is required
+/// - collections are encoded `as comma`, `as repeat` (default), or `as json`
+///
+/// The name is used as the query key as written, e.g., `startTime`, and the setter is
+/// its snake case, e.g., `start_time()`; it can't be one of the builder's own methods, i.e.,
+/// `get()` or `url()`, nor another parameter's.
+pub struct Param {
+    pub name: Ident,
+    pub ty: Type,
+    pub style: Option<Ident>,
+}
+
+impl Parse for Param {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty: Type = input.parse()?;
+
+        // `as comma`, `as repeat`, or `as json`
+        let style = if input.peek(Token![as]) {
+            input.parse::<Token![as]>()?;
+            let style: Ident = input.parse()?;
+            match style.to_string().as_str() {
+                "comma" | "repeat" | "json" => Some(style),
+                _ => {
+                    return Err(syn::Error::new(
+                        style.span(),
+                        "expected one of `comma`, `repeat`, or `json`",
+                    ))
+                }
+            }
+        } else {
+            None
+        };
+
+        let param = Self { name, ty, style };
+        let setter = param.setter_key();
+        if RESERVED.contains(&setter.as_str()) {
+            return Err(syn::Error::new(
+                param.name.span(),
+                format!("`{setter}()` is a method of the request builder, not a setter"),
+            ));
+        }
+        Ok(param)
+    }
+}
+
+// the request builder's own methods; no setter can share their names
+const RESERVED: &[&str] = &[
+    "get",
+    "post",
+    "put",
+    "patch",
+    "delete",
+    "head",
+    "get_bytes",
+    "get_text",
+    "get_as",
+    "get_response",
+    "get_with_meta",
+    "get_many",
+    "pages",
+    "items",
+    "watch",
+    "with_timeout",
+    "url",
+    "client",
+    "api",
+    "build",
+    "call",
+    "get_call",
+];
+
+/// Each parameter of an entry has its own setter, i.e., not both `startTime` & `start_time`.
+pub fn check_setters(params: &[Param]) -> syn::Result<()> {
+    for (i, param) in params.iter().enumerate() {
+        let setter = param.setter_key();
+        if let Some(other) = params[..i].iter().find(|p| p.setter_key() == setter) {
+            return Err(syn::Error::new(
+                param.name.span(),
+                format!(
+                    "`{}` & `{}` would both be set with `{setter}()`",
+                    other.key(),
+                    param.key()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+impl Param {
+    /// The key in the query string, e.g., `startTime`.
+    pub fn key(&self) -> String {
+        self.name.to_string().trim_start_matches("r#").to_string()
+    }
+
+    /// `Option<T>` parameters can be left out.
+    pub fn is_required(&self) -> bool {
+        option_inner(&self.ty).is_none()
+    }
+
+    // the name of the builder's setter, i.e., `start_time`
+    pub(crate) fn setter_name(&self) -> Ident {
+        let key = self.key();
+        if key == key.to_case(Case::Snake) {
+            self.name.clone()
+        } else {
+            format_ident!("{}", key.to_case(Case::Snake))
+        }
+    }
+
+    // the setter's name, without any `r#`
+    fn setter_key(&self) -> String {
+        let name = self.setter_name().to_string();
+        name.trim_start_matches("r#").to_string()
+    }
+
+    // the builder's setter for this parameter
+    pub(crate) fn setter(&self) -> TokenStream {
+        let key = self.key();
+        let name = self.setter_name();
+        let ty = option_inner(&self.ty).unwrap_or(&self.ty);
+        let style = match self.style.as_ref().map(|s| s.to_string()).as_deref() {
+            Some("comma") => quote!(kvapi::query::Style::Comma),
+            Some("json") => quote!(kvapi::query::Style::Json),
+            _ => quote!(kvapi::query::Style::Repeat),
+        };
+
+        quote! {
+            pub fn #name(mut self, #name: #ty) -> Self {
+                self.params.set(#key, &#name, #style);
+                self
+            }
+        }
+    }
+}
+
+// `T` of an `Option<T>`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
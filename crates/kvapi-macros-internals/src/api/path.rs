@@ -0,0 +1,149 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_str, Ident, LitStr, Type};
+
+/// The endpoint of a Dict entry, with any path parameters pulled out.
+///
+/// ```rust
+/// "series/{series_id}/observations": Observations,
+/// "users/{id: u32}/posts.json": Posts,
+/// ```
+///
+/// Each `{placeholder}` becomes an argument of the generated HTTP methods,
+/// e.g., `api.series.observations.get("DGS10")`; the type defaults to `&str`.
+/// A placeholder after the `?`, i.e., `"ticker/price?symbol={symbol}"`, is encoded as a query
+/// value, so it can't add parameters of its own.
+///
+/// Literal braces can be escaped as `{{` and `}}`.
+pub struct Template {
+    pub format: String, // `format!` string, with each placeholder replaced by `{}`
+    pub name: String,   // endpoint without placeholders or query; used for naming the fields
+    pub params: Vec<PathParam>, // in order of appearance
+}
+
+/// A single `{name: Type}` placeholder.
+pub struct PathParam {
+    pub name: Ident,
+    pub ty: Type,
+    pub query: bool, // after the `?`
+}
+
+impl Template {
+    pub fn parse(endpoint: &LitStr) -> syn::Result<Self> {
+        let error = |msg: String| syn::Error::new(endpoint.span(), msg);
+
+        let value = endpoint.value();
+        let mut format = String::new();
+        let mut name = String::new();
+        let mut params: Vec<PathParam> = vec![];
+        let mut query = false;
+
+        let mut chars = value.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                // escaped braces
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    format.push_str("{{");
+                    name.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    format.push_str("}}");
+                    name.push('}');
+                }
+
+                // >> placeholder
+                // everything up to the closing brace is `name` or `name: Type`.
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => inner.push(c),
+                            None => {
+                                return Err(error(format!(
+                                    "unclosed path parameter `{{{inner}` in \"{value}\""
+                                )))
+                            }
+                        }
+                    }
+
+                    let mut param = PathParam::parse(&inner).map_err(error)?;
+                    param.query = query;
+                    if params.iter().any(|p| p.name == param.name) {
+                        return Err(error(format!(
+                            "path parameter `{}` is declared more than once",
+                            param.name
+                        )));
+                    }
+                    params.push(param);
+                    format.push_str("{}");
+                }
+                '}' => {
+                    return Err(error(format!(
+                        "unmatched `}}` in \"{value}\"; use `}}}}` for a literal brace"
+                    )))
+                }
+                c => {
+                    query |= c == '?';
+                    format.push(c);
+                    name.push(c);
+                }
+            }
+        }
+
+        // any query in the key isn't part of the name, i.e., `"exchangeInfo?symbol=BNBBTC"`
+        if let Some((path, _)) = name.split_once('?') {
+            name = path.to_string();
+        }
+
+        Ok(Self {
+            format,
+            name,
+            params,
+        })
+    }
+
+    // the statement `let url = format!(...);`, percent-encoding each parameter as a path
+    // segment, or a query value
+    pub(crate) fn build(&self) -> TokenStream {
+        let format = &self.format;
+        let values = self.params.iter().map(|param| {
+            let name = &param.name;
+            match param.query {
+                true => quote!( kvapi::url::encode_query(&#name) ),
+                false => quote!( kvapi::url::encode_path(&#name) ),
+            }
+        });
+        quote! {
+            let url = format!(#format, #( #values ),*);
+        }
+    }
+}
+
+impl PathParam {
+    fn parse(input: &str) -> Result<Self, String> {
+        let (name, ty) = match input.split_once(':') {
+            Some((name, ty)) => (name.trim(), Some(ty.trim())),
+            None => (input.trim(), None),
+        };
+
+        if name.is_empty() {
+            return Err("empty path parameter `{}`; expected `{name}` or `{name: Type}`".into());
+        }
+        let name = parse_str::<Ident>(name)
+            .map_err(|_| format!("path parameter `{name}` is not a valid identifier"))?;
+        let ty = match ty {
+            Some(ty) => parse_str::<Type>(ty)
+                .map_err(|_| format!("unknown type `{ty}` for path parameter `{name}`"))?,
+            None => parse_str::<Type>("&str").expect("`&str` is a type"),
+        };
+
+        Ok(Self {
+            name,
+            ty,
+            query: false,
+        })
+    }
+}
@@ -0,0 +1,56 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, LitInt, Token,
+};
+
+/// Requests allowed per period, shared by every endpoint of an API.
+///
+/// ```rust
+/// rate_limit: 1200/min
+/// rate_limit: 10/sec
+/// ```
+///
+/// The period is one of `sec`, `min`, `hour`, or `day`; a Dict entry takes `#[weight: n]` of it.
+pub struct RateLimit {
+    pub capacity: u32,
+    pub seconds: u64,
+}
+
+impl Parse for RateLimit {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let requests: LitInt = input.parse()?;
+        input.parse::<Token![/]>()?;
+        let period: Ident = input.parse()?;
+
+        let seconds = match period.to_string().as_str() {
+            "s" | "sec" | "second" => 1,
+            "m" | "min" | "minute" => 60,
+            "h" | "hour" => 60 * 60,
+            "d" | "day" => 24 * 60 * 60,
+            _ => {
+                return Err(syn::Error::new(
+                    period.span(),
+                    "unknown period; expected one of `sec`, `min`, `hour`, or `day`",
+                ))
+            }
+        };
+        let capacity = requests.base10_parse()?;
+        if capacity == 0 {
+            return Err(syn::Error::new(
+                requests.span(),
+                "a rate limit allows at least one request",
+            ));
+        }
+        Ok(Self { capacity, seconds })
+    }
+}
+
+impl RateLimit {
+    // e.g., `kvapi::RateLimit::new(1200, std::time::Duration::from_secs(60))`
+    pub(crate) fn build(&self) -> TokenStream {
+        let (capacity, seconds) = (self.capacity, self.seconds);
+        quote!( kvapi::RateLimit::new(#capacity, std::time::Duration::from_secs(#seconds)) )
+    }
+}
@@ -0,0 +1,190 @@
+use super::common::{build_duration, duration, Separator};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    bracketed, parenthesized,
+    parse::{Parse, ParseStream},
+    Ident, LitBool, LitInt, Token,
+};
+
+/// The retry policy of an API, or of a single Dict entry's.
+///
+/// ```rust
+/// retry: { attempts: 5, backoff: exponential(200ms, 10s), on: [429, 500..=599, timeout, connect] }
+///
+/// #[retry(attempts: 3, backoff: constant(1s), non_idempotent: true)]
+/// "orders": CreateOrder => OrderAck,
+/// ```
+///
+/// - `attempts` counts the first request; it's required
+/// - `backoff` is `exponential(base, max)`, or `constant(delay)`
+/// - `on` lists the statuses (or ranges), and `timeout` or `connect` failures, to retry
+/// - `non_idempotent` retries `POST` & `PATCH` too
+/// - `max_wait` is the longest `Retry-After` to wait for; a minute, if not given
+///
+/// An entry's policy replaces the API's.
+pub struct Retry {
+    pub attempts: u32,
+    pub backoff: Option<Backoff>,
+    pub on: Option<Vec<On>>,
+    pub non_idempotent: bool,
+    pub max_wait: Option<u64>, // milliseconds
+}
+
+/// Delays in milliseconds.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Backoff {
+    Constant(u64),
+    Exponential(u64, u64),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum On {
+    Status(u16, u16),
+    Timeout,
+    Connect,
+}
+
+impl Parse for Retry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attempts: Option<u32> = None;
+        let mut backoff: Option<Backoff> = None;
+        let mut on: Option<Vec<On>> = None;
+        let mut non_idempotent = false;
+        let mut max_wait: Option<u64> = None;
+
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Separator>()?;
+            match key.to_string().as_str() {
+                "attempts" => attempts = Some(input.parse::<LitInt>()?.base10_parse()?),
+                "backoff" => backoff = Some(input.parse()?),
+                "non_idempotent" => non_idempotent = input.parse::<LitBool>()?.value,
+                "max_wait" => max_wait = Some(duration(&input.parse()?)?),
+
+                // `[429, 500..=599, timeout, connect]`
+                "on" => {
+                    let content;
+                    bracketed!(content in input);
+                    let list = content.parse_terminated(On::parse, Token![,])?;
+                    on = Some(list.into_iter().collect());
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown retry input; expected one of `attempts`, `backoff`, `on`, \
+                         `non_idempotent`, or `max_wait`",
+                    ))
+                }
+            }
+            input.parse::<Option<Token![,]>>()?;
+        }
+
+        let Some(attempts) = attempts else {
+            return Err(syn::Error::new(
+                input.span(),
+                "retry requires `attempts`, counting the first request",
+            ));
+        };
+        Ok(Self {
+            attempts,
+            backoff,
+            on,
+            non_idempotent,
+            max_wait,
+        })
+    }
+}
+
+impl Parse for Backoff {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let kind: Ident = input.parse()?;
+        let content;
+        parenthesized!(content in input);
+        let first = duration(&content.parse()?)?;
+        match kind.to_string().as_str() {
+            "constant" => Ok(Self::Constant(first)),
+            "exponential" => {
+                content.parse::<Token![,]>()?;
+                let max = duration(&content.parse()?)?;
+                Ok(Self::Exponential(first, max))
+            }
+            _ => Err(syn::Error::new(
+                kind.span(),
+                "unknown backoff; expected `exponential(base, max)`, or `constant(delay)`",
+            )),
+        }
+    }
+}
+
+impl Parse for On {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Ident) {
+            let kind: Ident = input.parse()?;
+            return match kind.to_string().as_str() {
+                "timeout" => Ok(Self::Timeout),
+                "connect" => Ok(Self::Connect),
+                _ => Err(syn::Error::new(
+                    kind.span(),
+                    "expected a status, a range of them, `timeout`, or `connect`",
+                )),
+            };
+        }
+
+        // `429`, or `500..=599`
+        let from: LitInt = input.parse()?;
+        let to = match input.peek(Token![..=]) {
+            true => {
+                input.parse::<Token![..=]>()?;
+                input.parse::<LitInt>()?
+            }
+            false => from.clone(),
+        };
+        let (from, to) = (from.base10_parse()?, to.base10_parse()?);
+        if !(100..=599).contains(&from) || !(from..=599).contains(&to) {
+            return Err(syn::Error::new(input.span(), "invalid status, or range"));
+        }
+        Ok(Self::Status(from, to))
+    }
+}
+
+impl Backoff {
+    // e.g., `kvapi::retry::Backoff::exponential(..)`
+    pub(crate) fn build(&self) -> TokenStream {
+        match *self {
+            Self::Constant(delay) => {
+                let delay = build_duration(delay);
+                quote!( kvapi::retry::Backoff::constant(#delay) )
+            }
+            Self::Exponential(base, max) => {
+                let (base, max) = (build_duration(base), build_duration(max));
+                quote!( kvapi::retry::Backoff::exponential(#base, #max) )
+            }
+        }
+    }
+}
+
+impl Retry {
+    // e.g., `kvapi::Retry::new(5).backoff(..).on([..])`
+    pub(crate) fn build(&self) -> TokenStream {
+        let attempts = self.attempts;
+        let backoff = self.backoff.as_ref().map(|backoff| {
+            let backoff = backoff.build();
+            quote!( .backoff(#backoff) )
+        });
+        let on = self.on.as_ref().map(|on| {
+            let on = on.iter().map(|on| match *on {
+                On::Status(from, to) => quote!( kvapi::retry::On::Status(#from, #to) ),
+                On::Timeout => quote!(kvapi::retry::On::Timeout),
+                On::Connect => quote!(kvapi::retry::On::Connect),
+            });
+            quote!( .on([#( #on ),*]) )
+        });
+        let non_idempotent = self.non_idempotent.then(|| quote!( .non_idempotent(true) ));
+        let max_wait = self.max_wait.map(|max_wait| {
+            let max_wait = build_duration(max_wait);
+            quote!( .max_wait(#max_wait) )
+        });
+        quote!( kvapi::Retry::new(#attempts) #backoff #on #non_idempotent #max_wait )
+    }
+}
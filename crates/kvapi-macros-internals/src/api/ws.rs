@@ -0,0 +1,267 @@
+use super::{
+    common::{build_duration, duration, Separator},
+    path::Template,
+    retry::Backoff,
+};
+use convert_case::{Case, Casing};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    Ident, LitInt, LitStr, Token, Type,
+};
+
+/// The WebSocket feed of an API.
+///
+/// ```rust
+/// ws: {
+///     url: "wss://stream.binance.com:9443/ws",
+///     subscribe: r#"{"method": "SUBSCRIBE", "params": ["{stream}"], "id": 1}"#,
+///     unsubscribe: r#"{"method": "UNSUBSCRIBE", "params": ["{stream}"], "id": 1}"#,
+///     ping: 3m,                                // or `{ every: 20s, message: r#"{"op": "ping"}"# }`
+///     reconnect: exponential(500ms, 30s),
+///     streams: {
+///         "{symbol}@trade": Trade,
+///         "!miniTicker@arr": Vec<MiniTicker>,
+///     },
+/// }
+/// ```
+///
+/// - `url` & `streams` are required
+/// - `subscribe` & `unsubscribe` are message templates, with `{stream}` as the stream's name
+/// - `ping` & `reconnect` delays are in milliseconds
+///
+/// Each stream is a field of `api.ws`, named by its key without any placeholders, i.e.,
+/// `api.ws.trade.subscribe("btcusdt")`; the placeholders are its arguments, as in a Dict entry.
+pub struct Ws {
+    pub url: String,
+    pub subscribe: Option<String>,
+    pub unsubscribe: Option<String>,
+    pub ping: Option<(u64, Option<String>)>,
+    pub reconnect: Option<Backoff>,
+    pub streams: Vec<WsStream>,
+}
+
+/// A single `"name": Type` of `streams`.
+pub struct WsStream {
+    pub key: String,
+    pub field: Ident,
+    pub template: Template,
+    pub ty: Type,
+}
+
+impl Parse for Ws {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+        let content;
+        braced!(content in input);
+
+        let mut url: Option<String> = None;
+        let mut subscribe: Option<String> = None;
+        let mut unsubscribe: Option<String> = None;
+        let mut ping: Option<(u64, Option<String>)> = None;
+        let mut reconnect: Option<Backoff> = None;
+        let mut streams: Option<Vec<WsStream>> = None;
+
+        while !content.is_empty() {
+            let key: Ident = content.parse()?;
+            content.parse::<Separator>()?;
+            match key.to_string().as_str() {
+                "url" => url = Some(content.parse::<LitStr>()?.value()),
+                "subscribe" => subscribe = Some(content.parse::<LitStr>()?.value()),
+                "unsubscribe" => unsubscribe = Some(content.parse::<LitStr>()?.value()),
+                "ping" => ping = Some(parse_ping(&content)?),
+                "reconnect" => reconnect = Some(content.parse()?),
+                "streams" => {
+                    let inner;
+                    braced!(inner in content);
+                    let list = inner.parse_terminated(WsStream::parse, Token![,])?;
+                    streams = Some(list.into_iter().collect());
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown ws input; expected one of `url`, `subscribe`, `unsubscribe`, \
+                         `ping`, `reconnect`, or `streams`",
+                    ))
+                }
+            }
+            content.parse::<Option<Token![,]>>()?;
+        }
+
+        let Some(url) = url else {
+            return Err(syn::Error::new(span, "ws requires the `url` to connect to"));
+        };
+        let streams = streams.unwrap_or_default();
+        if streams.is_empty() {
+            return Err(syn::Error::new(
+                span,
+                "ws requires at least one of `streams`",
+            ));
+        }
+        for (i, stream) in streams.iter().enumerate() {
+            if streams[..i].iter().any(|s| s.field == stream.field) {
+                return Err(syn::Error::new(
+                    stream.field.span(),
+                    format!("more than one stream is named `{}`", stream.field),
+                ));
+            }
+        }
+        if unsubscribe.is_some() && subscribe.is_none() {
+            return Err(syn::Error::new(
+                span,
+                "an `unsubscribe` message needs the `subscribe` one",
+            ));
+        }
+
+        Ok(Self {
+            url,
+            subscribe,
+            unsubscribe,
+            ping,
+            reconnect,
+            streams,
+        })
+    }
+}
+
+// `3m`, or `{ every: 20s, message: ".." }`
+fn parse_ping(input: ParseStream) -> syn::Result<(u64, Option<String>)> {
+    if input.peek(LitInt) {
+        return Ok((duration(&input.parse()?)?, None));
+    }
+
+    let span = input.span();
+    let content;
+    braced!(content in input);
+    let mut every: Option<u64> = None;
+    let mut message: Option<String> = None;
+    while !content.is_empty() {
+        let key: Ident = content.parse()?;
+        content.parse::<Separator>()?;
+        match key.to_string().as_str() {
+            "every" => every = Some(duration(&content.parse()?)?),
+            "message" => message = Some(content.parse::<LitStr>()?.value()),
+            _ => {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "unknown ping input; expected one of `every`, or `message`",
+                ))
+            }
+        }
+        content.parse::<Option<Token![,]>>()?;
+    }
+    match every {
+        Some(every) => Ok((every, message)),
+        None => Err(syn::Error::new(span, "a ping requires how often, `every`")),
+    }
+}
+
+impl Parse for WsStream {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lit: LitStr = input.parse()?;
+        input.parse::<Separator>()?;
+        let ty: Type = input.parse()?;
+
+        let template = Template::parse(&lit)?;
+        // i.e., `"btcusdt@bookTicker"` -> `btcusdt_book_ticker`
+        let words: Vec<&str> = template
+            .name
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        if words.is_empty() {
+            return Err(syn::Error::new(
+                lit.span(),
+                "a stream needs a name besides its placeholders",
+            ));
+        }
+        let field = words.join("_").to_case(Case::Snake);
+        let field = syn::parse_str::<Ident>(&field)
+            .map_err(|_| syn::Error::new(lit.span(), format!("`{field}` isn't a valid name")))?;
+
+        Ok(Self {
+            key: lit.value(),
+            field,
+            template,
+            ty,
+        })
+    }
+}
+
+impl Ws {
+    // e.g., `kvapi::Socket::new("wss://..").subscribe_message(..).ping(..)`
+    pub(crate) fn build_socket(&self) -> TokenStream {
+        let url = &self.url;
+        let subscribe = self
+            .subscribe
+            .as_ref()
+            .map(|subscribe| quote!( .subscribe_message(#subscribe) ));
+        let unsubscribe = self
+            .unsubscribe
+            .as_ref()
+            .map(|unsubscribe| quote!( .unsubscribe_message(#unsubscribe) ));
+        let ping = self.ping.as_ref().map(|(every, message)| {
+            let every = build_duration(*every);
+            match message {
+                Some(message) => quote!( .ping_message(#every, #message) ),
+                None => quote!( .ping(#every) ),
+            }
+        });
+        let reconnect = self.reconnect.as_ref().map(|backoff| {
+            let backoff = backoff.build();
+            quote!( .reconnect(#backoff) )
+        });
+        quote!( kvapi::Socket::new(#url) #subscribe #unsubscribe #ping #reconnect )
+    }
+
+    // `#ws`, with a field of each stream; and each stream's struct, with its `subscribe()`
+    pub(crate) fn build(&self, ws: &Ident) -> TokenStream {
+        let fields: Vec<&Ident> = self.streams.iter().map(|stream| &stream.field).collect();
+        let structs: Vec<Ident> = self
+            .streams
+            .iter()
+            .map(|stream| {
+                let pascal = stream.field.to_string().to_case(Case::Pascal);
+                format_ident!("{}{}", ws, pascal)
+            })
+            .collect();
+
+        let streams = self.streams.iter().zip(&structs).map(|(stream, name)| {
+            let key = &stream.key;
+            let ty = &stream.ty;
+            let format = &stream.template.format;
+            let names: Vec<&Ident> = stream.template.params.iter().map(|p| &p.name).collect();
+            let types = stream.template.params.iter().map(|p| &p.ty);
+            let doc = format!("Subscribe to `\"{key}\"`.");
+            quote! {
+                pub struct #name {
+                    socket: std::sync::Arc<kvapi::Socket>,
+                }
+                impl #name {
+                    #[doc = #doc]
+                    pub fn subscribe(&self, #( #names: #types ),*) -> kvapi::Subscription<'_, #ty> {
+                        let stream = format!(#format, #( #names ),*);
+                        self.socket.subscribe(#key, stream)
+                    }
+                }
+            }
+        });
+
+        quote! {
+            pub struct #ws {
+                #( pub #fields: #structs, )*
+            }
+            impl #ws {
+                fn new(socket: kvapi::Socket) -> Self {
+                    let socket = std::sync::Arc::new(socket);
+                    Self {
+                        #( #fields: #structs { socket: socket.clone() }, )*
+                    }
+                }
+            }
+            #( #streams )*
+        }
+    }
+}
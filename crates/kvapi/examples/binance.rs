@@ -1,36 +1,74 @@
 use dotenv::dotenv;
+use kvapi::futures::StreamExt;
+use serde::Deserialize;
 use serde_json::Value;
+use std::time::Duration;
 
 // Crypto brokers tend to have pretty modern APIs, as does Binance in the example below.
 //
 // Some notes about this example:
-//      >> the API key is included in the header of the client (and retrieved from our '.env' file);
-//      >> 'serde_json::Value' is used as an easy way of exploring the API without having defined the schema, yet.
+//      >> the API key is sent in the `X-MBX-APIKEY` header of each request (and retrieved from our '.env' file);
+//      >> 'serde_json::Value' is used as an easy way of exploring the API without having defined the schema, yet;
+//      >> any non-2xx response is decoded into `BinanceError`, i.e., `{"code":-1121,"msg":"Invalid symbol."}`;
+//      >> requests are throttled to Binance's 1200 weight per minute; `exchangeInfo` weighs 20;
+//      >> a request that takes over 10s fails with `kvapi::Error::Timeout`;
+//      >> `watch()` polls the ticker, yielding only the prices that changed;
+//      >> `ws:` declares the WebSocket streams, i.e., `bnc.ws.trade.subscribe("btcusdt")` (needs the `ws` feature).
 //
 // API Documentation:
 //      >> "https://binance-docs.github.io/apidocs/spot/en/#introduction"
 //      >> "https://binance-docs.github.io/apidocs/spot/en/#market-data-endpoints"
+//      >> "https://binance-docs.github.io/apidocs/spot/en/#websocket-market-streams"
 kvapi::api! {
    name:       Binance
    base:       "https://api.binance.com/api/v3/"
-   headers:    {
-                   "X-MBX-APIKEY": &key("BINANCE_API"),
-                   //              ^ header values must be a referenced String/str
-               }
+   auth:       ApiKey { in: header, name: "X-MBX-APIKEY", from: env "BINANCE_API" }
+   error:      BinanceError
+   rate_limit: 1200/min
+   timeout:    10s
    dict:       {
                    "ping": Value,
+
+                   // query parameters are set per request, e.g., `.request().symbol("BNBBTC")`;
+                   // `symbols` is sent as a JSON array: `symbols=["BTCUSDT","BNBBTC"]`
+                   #[params(symbol: Option<&str>, symbols: Option<Vec<&str>> as json), weight: 20]
                    "exchangeInfo" : Value,
-                   #[rename: "BNB_BTC"]    "exchangeInfo?symbol=BNBBTC": Value,
-                   #[rename: "sui"]        "ticker/price?symbol=SUIUSDT": Value,
-                   #[rename: "sui_btc"]    "ticker/price?symbol=SUIBTC": Value,
-                   #[rename: "basket"]     "exchangeInfo?symbols=[\"BTCUSDT\",\"BNBBTC\"]": Value,
-                   //                                             ^^       ^^ ^^      ^^
-                   //                      speech quotes needed in the URL are handled with the usual backslash
+
+                   #[rename: "BNB_BTC", weight: 20]    "exchangeInfo?symbol=BNBBTC": Value,
+
+                   #[params(symbol: &str)]
+                   "ticker/price": Value,
+               }
+   // the server pings every 3 minutes, and is answered on its own
+   ws:         {
+                   url: "wss://stream.binance.com:9443/ws",
+                   subscribe: r#"{"method": "SUBSCRIBE", "params": ["{stream}"], "id": 1}"#,
+                   unsubscribe: r#"{"method": "UNSUBSCRIBE", "params": ["{stream}"], "id": 1}"#,
+                   streams: {
+                       "{symbol}@trade": Trade,
+                       "!miniTicker@arr": Vec<Value>,
+                   },
                }
 }
 
-pub fn key(var: &str) -> String {
-    std::env::var(var).expect("failed to find BINANCE_API in .env")
+// https://binance-docs.github.io/apidocs/spot/en/#trade-streams
+#[derive(Debug, Deserialize)]
+pub struct Trade {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "p")]
+    pub price: String,
+    #[serde(rename = "q")]
+    pub quantity: String,
+    #[serde(rename = "T")]
+    pub time: u64,
+}
+
+// https://binance-docs.github.io/apidocs/spot/en/#error-codes
+#[derive(Debug, Deserialize)]
+pub struct BinanceError {
+    pub code: i64,
+    pub msg: String,
 }
 
 #[tokio::main]
@@ -46,6 +84,56 @@ async fn main() {
     bnc.bnb_btc.dbg_client();
 
     println!("{:#?}", bnc.bnb_btc.get().await.unwrap());
-    println!("{:#?}", bnc.basket.get().await.unwrap());
-    println!("{:#?}", bnc.sui.get().await.unwrap());
+
+    // one entry, many symbols
+    let basket = bnc
+        .exchange_info
+        .request()
+        .symbols(vec!["BTCUSDT", "BNBBTC"]);
+    println!("{:#?}", basket.get().await.unwrap());
+    // one entry, each symbol; a few requests at a time, within the rate limit
+    let prices = bnc
+        .ticker
+        .price
+        .get_many(["SUIUSDT", "SUIBTC", "BTCUSDT"])
+        .concurrency(2)
+        .await;
+    for price in prices {
+        println!("{:#?}", price);
+    }
+
+    // the API's own error
+    match bnc.ticker.price.request().symbol("NOTASYMBOL").get().await {
+        Err(kvapi::Error::Api(e)) => {
+            let error: &BinanceError = e.downcast_ref().unwrap();
+            println!("{} -> {} {}", e.status, error.code, error.msg);
+        }
+        other => println!("{:#?}", other),
+    }
+
+    // the price of BTC, polled every second, as it changes; for 10s
+    let token = kvapi::CancellationToken::new();
+    let btc = bnc.ticker.price.request().symbol("BTCUSDT");
+    let mut btc = btc
+        .watch(Duration::from_secs(1))
+        .distinct()
+        .until(token.clone());
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        token.cancel();
+    });
+    while let Some(price) = btc.next().await {
+        println!("{:?}", price.map(|price| price["price"].clone()));
+    }
+
+    // every trade of BTC, as it happens; for 10s
+    let token = kvapi::CancellationToken::new();
+    let mut trades = bnc.ws.trade.subscribe("btcusdt").until(token.clone());
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        token.cancel();
+    });
+    while let Some(trade) = trades.next().await {
+        println!("{:?}", trade);
+    }
 }
@@ -1,5 +1,3 @@
-mod schema;
-
 use dotenv::{dotenv, var};
 use serde_json::Value;
 
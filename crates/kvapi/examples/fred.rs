@@ -1,8 +1,8 @@
 pub mod schema;
 
 use dotenv::dotenv;
-use kvapi::{api, Http, HttpMethods};
-use schema::fred::{Observations, Releases, Series, Sources};
+use kvapi::{api, futures::TryStreamExt};
+use schema::fred::{Observation, SeriesEntry, Source};
 
 //////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -13,53 +13,63 @@ use schema::fred::{Observations, Releases, Series, Sources};
 api! {
     name:   Fred
     base:   "https://api.stlouisfed.org/fred"
-    query:  format!("api_key={}&file_type=json", key()) // global query, appended to all urls
+    query:  "file_type=json" // global query, merged into all urls
+
+    // the key is added to the query of each request as it's sent, so it's kept out of
+    // `dbg_url()`, and out of the URL of any error; `.env` file needed with "FRED_API= ..." key
+    auth:   ApiKey { in: query, name: "api_key", from: env "FRED_API" }
     dict:   {
-                // these queries are key-specific, and are appended before the above (global) query.
+                // each response wraps a list in a named array, i.e., `{"count": 45, "observations": [...]}`;
+                // the `envelope` of an entry names it, so the type is only the list.
+                //
+                // these queries are key-specific, and are merged after the above (global) query;
+                // the '?' and '&' separators are handled for us.
                 //
                 // in this example, category_id=125 would give us Trade Balance data, but the
                 // general endpoint would be the same for many datasets.
                 //
                 // 'rename' and 'query' are useful for these types of APIs.
                 //
-                // final query: "https://api.stlouisfed.org/fred?category_id=125&api_key={API_KEY}&file_type=json"
-                #[query: "?category_id=125&", rename: "trade_balance"]
-                "/category/series": Series,
+                // final url: "https://api.stlouisfed.org/fred/category/series?file_type=json&category_id=125&api_key={API_KEY}"
+                // (there's an extra 's' in "seriess", in the API, for some reason)
+                #[query: "category_id=125", rename: "trade_balance", envelope(data: "seriess")]
+                "/category/series": Vec<SeriesEntry>,
 
                 // setting category_id=100 gives us an array of other datasets, so
                 // we can rename it to `other` and explore it.
-                #[query: "?category_id=100&", rename: "other"]
-                "/category/series": Series,
+                #[query: "category_id=100", rename: "other", envelope(data: "seriess")]
+                "/category/series": Vec<SeriesEntry>,
+
+                // every series of a category (Money, Banking, & Finance), 1000 at a time; `items()`
+                // follows the `offset` until the `count`, one page at a time.
+                #[query: "category_id=32991", rename: "money"]
+                #[paginate(offset = "offset", limit = "limit", size = 1000, total = "count", items = "seriess", item = SeriesEntry)]
+                "/category/series": kvapi::Value,
 
                 // all sources
-                #[query="?"] // this '?' is needed for the the global query to remain valid
-                "/sources": Sources,
+                #[envelope(data: "sources")]
+                "/sources": Vec<Source>,
 
                 // U.S. Employment and Training Administration
-                #[query="?source_id=50&", rename="employment"]
-                "/sources/releases": Releases,
+                #[query="source_id=50", rename="employment", envelope(data: "releases")]
+                "/sources/releases": Vec<Source>,
 
                 // 10 yr yield
-                #[query="?series_id=DGS10&", rename -> "ten_yr"]
-                "/series/observations": Observations,
+                #[query="series_id=DGS10", rename -> "ten_yr", envelope(data: "observations")]
+                "/series/observations": Vec<Observation>,
 
                 // unemployment rate
-                #[query: "?series_id=UNRATE&", rename: "unemployment"]
-                "/series/observations": Observations,
+                #[query: "series_id=UNRATE", rename: "unemployment", envelope(data: "observations")]
+                "/series/observations": Vec<Observation>,
 
                 // The Federal Reserve releases
-                #[query: "?source_id=1&", rename: "the_fed"]
-                "/source/releases": Releases,
+                #[query: "source_id=1", rename: "the_fed", envelope(data: "releases")]
+                "/source/releases": Vec<Source>,
             }
 }
 
 //////////////////////////////////////////////////////////////////////////////////////////////////////
 
-// `.env` file needed with "FRED_API= ..." key
-fn key() -> String {
-    std::env::var("FRED_API").expect("FRED_API not set")
-}
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv().ok();
@@ -69,30 +79,35 @@ async fn main() -> anyhow::Result<()> {
     let fred: Fred = Fred::new();
 
     // `.dbg_url()` prints the url that `trade_balance` has created under the hood
-    fred.trade_balance.dbg_url(); //
-                                  // // explore the `other` dataset
-    println!("{:#?}", fred.other.get().await?.series);
+    fred.trade_balance.dbg_url();
+
+    // explore the `other` dataset
+    println!("{:#?}", fred.other.get().await?);
+
+    // every page of the category, as one list
+    let money: Vec<SeriesEntry> = fred.money.items().try_collect().await?;
+    println!("{} series of Money, Banking, & Finance", money.len());
 
     // print all the "sources" section
-    for x in fred.sources.get().await?.sources {
+    for x in fred.sources.get().await? {
         println!(" {:04} | {}", x.id, x.name)
     }
 
     // print the url, then print all the dates & values
     fred.ten_yr.dbg_url();
-    for x in fred.ten_yr.get().await?.inner {
+    for x in fred.ten_yr.get().await? {
         println!("[{:<10}] {}", x.date, x.value)
     }
 
     // explore unemployment
     fred.unemployment.dbg_url();
-    for x in fred.unemployment.get().await?.inner {
+    for x in fred.unemployment.get().await? {
         println!("[{:<10}] {}", x.date, x.value)
     }
 
     // explore the Fed's releases
     fred.the_fed.dbg_url();
-    for x in fred.the_fed.get().await?.releases {
+    for x in fred.the_fed.get().await? {
         println!("<< {} >> [{:04}] {}", x.realtime_end, x.id, x.name)
     }
 
@@ -1,5 +1,3 @@
-mod schema;
-
 use dotenv::{dotenv, var};
 use serde_json::Value;
 
@@ -15,19 +13,34 @@ kvapi::api! {
                     // API-Key HTTP header parameter: the public key from your API key-pair
                     "API-Key": &var("KRAKEN_API").expect("failed to find KRAKEN_API env. var.")
 
-                    // API-Sign HTTP header parameter: encrypted signature of message.
-
-                    // nonce payload parameter: always increasing, unsigned 64-bit integer.
-
                     // otp payload parameter: one-time-password and is only required if additional 2FA is configured for API.
 
                 }
 
+    // private endpoints are signed as they're sent: the `nonce` is added to the body, and the
+    // API-Sign header is the signature of the path, nonce & body; public ones are left as is
+    middleware: [
+                    kvapi::signer::Kraken::new(
+                        var("KRAKEN_API").unwrap_or_default(),
+                        var("KRAKEN_PRIVATE").unwrap_or_default(),
+                    ),
+                ]
+
+    // every response is `{"error": [...], "result": {...}}`; `get()` returns the `result`,
+    // or fails if there's an `error`, even with an HTTP 200
+    envelope:   { data: "result", error: "error" }
+
     dict:       {
                     "Time": Value,
 
                     #[rename: "btc", query: "?pair=BTCUSDT&interval=1440"]
                     "OHLC": Value,
+
+                    #[rename: "unknown", query: "?pair=NOTAPAIR"]
+                    "Ticker": Value,
+
+                    #[rename: "balance", method: POST, body: form]
+                    "https://api.kraken.com/0/private/Balance": Value,
                 }
 }
 
@@ -41,5 +54,11 @@ async fn main() -> anyhow::Result<()> {
     println!("{:#?}", kraken.time.get().await?);
     println!("{:#?}", kraken.btc.get().await?);
 
+    // i.e., `kraken.unknown` reported an error: {"error":["EQuery:Unknown asset pair"]}
+    println!("{}", kraken.unknown.get().await.unwrap_err());
+
+    // signed with KRAKEN_API & KRAKEN_PRIVATE
+    println!("{:#?}", kraken.balance.post(&serde_json::json!({})).await);
+
     Ok(())
 }
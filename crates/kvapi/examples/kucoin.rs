@@ -1,10 +1,6 @@
-mod schema;
-
-use base64::prelude::{Engine, BASE64_STANDARD};
 use dotenv::{dotenv, var};
-use hmac::{Hmac, Mac};
+use serde::Deserialize;
 use serde_json::Value;
-use sha2::Sha256;
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -12,30 +8,22 @@ use sha2::Sha256;
 kvapi::api! {
     name: KuCoin
     base: "https://api.kucoin.com/api/"
-    head: {
-              // https://www.kucoin.com/docs/basic-info/connection-method/authentication/creating-a-request
-              "KC-API-KEY": &var("KUCOIN_API")?,
-              "KC-API-KEY-VERSION": "2",
-
-              // the next 3 headers require the timestamp to be the same, so they're performed at
-              // the query level, for reusage
-              #[query]
-              "KC-API-TIMESTAMP": &timestamp(),
 
-              #[query]
-              "KC-API-PASSPHRASE": &encrypt(
-                  var("KUCOIN_PRIVATE").unwrap(),
-                  var("KUCOIN_PASSPHRASE").unwrap(),
+    // https://www.kucoin.com/docs/basic-info/connection-method/authentication/creating-a-request
+    // each request is signed as it's sent; the `KC-API-*` headers share one timestamp, and the
+    // signature covers the method, path, query & body
+    middleware: [
+              kvapi::signer::KuCoin::new(
+                  var("KUCOIN_API").unwrap_or_default(),
+                  var("KUCOIN_PRIVATE").unwrap_or_default(),
+                  var("KUCOIN_PASSPHRASE").unwrap_or_default(),
               ),
+          ]
 
-              #[query]
-              "KC-API-SIGN": &sign(
-                  &self.url, // one can access the url & client of `self`
-                             // this could be risky if not used properly
-                  var("KUCOIN_PRIVATE").unwrap(),
-                  timestamp(),
-              ),
-          }
+    error: KuCoinError
+
+    // every response is `{"code": "200000", "data": ...}`; any other code is a `KuCoinError`
+    envelope: { data: "data", success: "code" == "200000" }
 
     dict: {
               #[rename: "timestamp"]
@@ -55,24 +43,11 @@ kvapi::api! {
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
-// Signing Documentation: https://www.kucoin.com/docs/basic-info/connection-method/authentication/signing-a-message
-fn encrypt(secret: String, input: String) -> String {
-    type HmacSha256 = Hmac<Sha256>;
-    let mut mac = HmacSha256::new_from_slice(&secret.as_bytes()).unwrap();
-    mac.update(input.as_bytes());
-    let result = mac.finalize().into_bytes();
-    let b64 = BASE64_STANDARD.encode(&result);
-    b64
-}
-
-fn sign(url: &String, secret: String, timestamp: String) -> String {
-    let url = url.replace("https://api.kucoin.com", "");
-    let input = format!("{}{}{}", timestamp, "GET", url);
-    encrypt(secret, input)
-}
-
-fn timestamp() -> String {
-    chrono::Utc::now().timestamp_millis().to_string()
+// https://www.kucoin.com/docs/errors/http
+#[derive(Debug, Deserialize)]
+pub struct KuCoinError {
+    pub code: String,
+    pub msg: String,
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -1,6 +1,5 @@
-mod schema;
-
 use dotenv::{dotenv, var};
+use serde::Deserialize;
 use serde_json::Value;
 
 ///////////////////////////////////////////////////////////////////////////////////////////
@@ -12,6 +11,8 @@ kvapi::api! {
     headers:    {
                     "apiKey": &var("MEXC_API").expect("failed to find MEXC_API"),
                 }
+    error:      MexcError
+    retry:      { attempts: 4, backoff: exponential(500ms, 8s), on: [429, 500..=599, timeout, connect] }
     dict:       {
                     "ping": Value,
 
@@ -23,19 +24,33 @@ kvapi::api! {
 
                     #[rename: "btc_orderbook", query: "?symbol=BTCUSDT"]
                     "depth": Value,
+
+                    #[rename: "unknown_orderbook", query: "?symbol=NOTASYMBOL"]
+                    "depth": Value,
                 }
 }
 
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#error-code
+#[derive(Debug, Deserialize)]
+pub struct MexcError {
+    pub code: i64,
+    pub msg: String,
+}
+
 ///////////////////////////////////////////////////////////////////////////////////////////
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
 
-    // each endpoint will have its own client, and all are initialised at the point of `new()`
+    // every endpoint shares one client, initialised at the point of `new()`
     let mexc = Mexc::new();
     println!("{:#?}", mexc.ping.get().await.unwrap());
     println!("{:#?}", mexc.exchange_info.get().await.unwrap());
     println!("{:#?}", mexc.kaspa_klines.get().await.unwrap());
     println!("{:#?}", mexc.btc_orderbook.get().await.unwrap());
+
+    // non-2xx responses are decoded into `MexcError`
+    let error = mexc.unknown_orderbook.get().await.unwrap_err();
+    println!("{:#?}", error.api::<MexcError>());
 }
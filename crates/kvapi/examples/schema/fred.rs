@@ -59,12 +59,6 @@ pub struct SeriesEntry {
     pub popularity: u32,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct Series {
-    #[serde(rename = "seriess")] // there's an extra 's' in the API (for some reason)
-    pub series: Vec<SeriesEntry>,
-}
-
 // {
 //     "realtime_start": "2013-08-14",
 //     "realtime_end": "2013-08-14",
@@ -80,11 +74,6 @@ pub struct Series {
 //             "realtime_end": "2013-08-14",
 //             "name": "Board of Governors of the Federal Reserve System",
 //             "link": "http://www.federalreserve.gov/"
-#[derive(Deserialize, Debug)]
-pub struct Sources {
-    pub sources: Vec<Source>,
-}
-
 #[derive(Deserialize, Debug)]
 pub struct Source {
     pub id: u32,
@@ -110,11 +99,6 @@ pub struct Source {
 //             "press_release": true,
 //             "link": "http://www.federalreserve.gov/releases/g17/"
 //         },
-#[derive(Deserialize, Debug)]
-pub struct Releases {
-    pub releases: Vec<Source>,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct Observation {
     pub realtime_start: String,
@@ -122,9 +106,3 @@ pub struct Observation {
     pub date: String,
     pub value: String,
 }
-
-#[derive(Debug, Deserialize)]
-pub struct Observations {
-    #[serde(rename = "observations")]
-    pub inner: Vec<Observation>,
-}
@@ -1,2 +1,5 @@
+// each example only uses the schema of its own API
+#![allow(dead_code)]
+
 pub mod fred;
 pub mod sec;
@@ -7,13 +7,19 @@ use schema::sec::Tickers;
 // A very simple example of the SEC's EDGAR API, in which we simply return the list of company tickers.
 // The `schema::sec` module is used to intricately define exact `serde` Deserialization for the JSON.
 // (It might shed some light on how to optimise `serde`, as well.)
+// EDGAR asks for at most 10 requests per second, which `rate_limit` keeps to; the tickers file
+// changes daily at most, so it's cached for a day, and revalidated after.
 //
 // API Documentation: https://www.sec.gov/search-filings/edgar-application-programming-interfaces
 api! {
     name: Sec
     base: "https://www.sec.gov/files/"
     head: { "User-Agent": &var("USER_AGENT")? }
-    dict: { "company_tickers.json" -> Tickers }
+    rate_limit: 10/sec
+    dict: {
+        #[cache: 1d]
+        "company_tickers.json" -> Tickers
+    }
 }
 
 #[tokio::main]
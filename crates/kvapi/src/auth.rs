@@ -0,0 +1,351 @@
+use crate::{decode, url::Url, Error, Result};
+use reqwest::{header::HeaderValue, Client, RequestBuilder};
+use serde::Deserialize;
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Credentials of an API, sent with every request, from `auth:` in `api!`.
+///
+/// ```rust,ignore
+/// auth: Bearer(env "TOKEN")
+/// auth: Basic(user, password)
+/// auth: ApiKey { in: header, name: "X-MBX-APIKEY", from: env "BINANCE_API" }
+/// auth: ApiKey { in: query, name: "api_key", from: env "FRED_API" }
+/// auth: OAuth2 { token_url: "https://..", client_id: env "ID", client_secret: env "SECRET" }
+/// ```
+///
+/// The secret is marked sensitive, and kept out of `Debug`; a key in the query is only added
+/// when the request is sent, so it's out of `dbg_url()`, and redacted from the URL of errors &
+/// responses, even the raw one of `get_response()`.
+#[derive(Clone, Debug)]
+pub enum Auth {
+    /// `Authorization: Bearer {token}`
+    Bearer(Secret),
+    /// `Authorization: Basic {base64(user:password)}`
+    Basic { user: String, password: Secret },
+    /// `{name}: {key}`, or `?{name}={key}`
+    ApiKey {
+        location: Location,
+        name: String,
+        key: Secret,
+    },
+    /// `Authorization: Bearer {access_token}`, fetched & renewed as needed; see [`OAuth2`].
+    OAuth2(Arc<OAuth2>),
+}
+
+/// Where an API key is sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Location {
+    Header,
+    Query,
+}
+
+impl Auth {
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Self::Bearer(Secret::new(token))
+    }
+
+    pub fn basic(user: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::Basic {
+            user: user.into(),
+            password: Secret::new(password),
+        }
+    }
+
+    /// An API key in the header `name`.
+    pub fn header(name: impl Into<String>, key: impl Into<String>) -> Self {
+        Self::ApiKey {
+            location: Location::Header,
+            name: name.into(),
+            key: Secret::new(key),
+        }
+    }
+
+    /// An API key in the query parameter `name`.
+    pub fn query(name: impl Into<String>, key: impl Into<String>) -> Self {
+        Self::ApiKey {
+            location: Location::Query,
+            name: name.into(),
+            key: Secret::new(key),
+        }
+    }
+
+    pub fn oauth2(oauth2: OAuth2) -> Self {
+        Self::OAuth2(Arc::new(oauth2))
+    }
+
+    // add the credentials to a request; an OAuth2 token is added by `Api::execute()`
+    pub(crate) fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            Self::Bearer(token) => request.bearer_auth(token.expose()),
+            Self::Basic { user, password } => request.basic_auth(user, Some(password.expose())),
+            Self::ApiKey {
+                location: Location::Header,
+                name,
+                key,
+            } => match HeaderValue::from_str(key.expose()) {
+                Ok(mut value) => {
+                    value.set_sensitive(true);
+                    request.header(name.as_str(), value)
+                }
+                // fails the request, without the key in the error
+                Err(_) => request.header(name.as_str(), key.expose()),
+            },
+            Self::ApiKey {
+                location: Location::Query,
+                name,
+                key,
+            } => request.query(&[(name, key.expose())]),
+            Self::OAuth2(_) => request,
+        }
+    }
+
+    // what tells these credentials apart from others, i.e., in the key of a cached response
+    pub(crate) fn identity(&self) -> Vec<&str> {
+        match self {
+            Self::Bearer(token) => vec!["bearer", token.expose()],
+            Self::Basic { user, password } => vec!["basic", user, password.expose()],
+            Self::ApiKey {
+                location,
+                name,
+                key,
+            } => {
+                let location = match location {
+                    Location::Header => "header",
+                    Location::Query => "query",
+                };
+                vec![location, name, key.expose()]
+            }
+            Self::OAuth2(oauth2) => {
+                let refresh = oauth2.refresh_token.as_ref().map(Secret::expose);
+                let mut identity = vec![
+                    "oauth2",
+                    &oauth2.token_url,
+                    &oauth2.client_id,
+                    oauth2.client_secret.expose(),
+                    refresh.unwrap_or_default(),
+                ];
+                identity.extend(oauth2.scopes.iter().map(String::as_str));
+                identity
+            }
+        }
+    }
+
+    // hide a key in the query of `url`, i.e., `?api_key=***&file_type=json`
+    pub(crate) fn redact(&self, url: &mut Url) {
+        let Self::ApiKey {
+            location: Location::Query,
+            name,
+            ..
+        } = self
+        else {
+            return;
+        };
+        if !url.query_pairs().any(|(key, _)| key == name.as_str()) {
+            return;
+        }
+
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(key, value)| match key == name.as_str() {
+                true => (key.into_owned(), "***".to_string()),
+                false => (key.into_owned(), value.into_owned()),
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+}
+
+/// OAuth2 access tokens, by the client-credentials or refresh-token grant.
+///
+/// ```rust,ignore
+/// auth: OAuth2 {
+///     token_url:      "https://auth.example.com/oauth/token",
+///     client_id:      env "CLIENT_ID",
+///     client_secret:  env "CLIENT_SECRET",
+///     scopes:         ["read", "write"],
+///     refresh_token:  env "REFRESH_TOKEN",   // optional; the refresh-token grant
+/// }
+/// ```
+///
+/// The token is fetched with the first request, and cached; it's renewed shortly before it
+/// expires, or once if a request is answered with a `401`. Every endpoint of an API shares the
+/// one token, and concurrent requests wait on a single renewal.
+///
+/// A refresh token returned by the server replaces the one given; without a refresh token, the
+/// client credentials are used.
+pub struct OAuth2 {
+    token_url: String,
+    client_id: String,
+    client_secret: Secret,
+    scopes: Vec<String>,
+    refresh_token: Option<Secret>,
+    token: Mutex<Option<Token>>,
+}
+
+#[derive(Clone)]
+struct Token {
+    access: Secret,
+    expires_at: Option<Instant>,
+    refresh: Option<Secret>,
+}
+
+// the token endpoint's response
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+impl OAuth2 {
+    // renew the token this long before it expires
+    const MARGIN: Duration = Duration::from_secs(30);
+
+    pub fn new(
+        token_url: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            token_url: token_url.into(),
+            client_id: client_id.into(),
+            client_secret: Secret::new(client_secret),
+            scopes: vec![],
+            refresh_token: None,
+            token: Mutex::new(None),
+        }
+    }
+
+    pub fn scopes<S: Into<String>>(mut self, scopes: impl IntoIterator<Item = S>) -> Self {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Use the refresh-token grant, starting with `refresh_token`.
+    pub fn refresh_token(mut self, refresh_token: impl Into<String>) -> Self {
+        self.refresh_token = Some(Secret::new(refresh_token));
+        self
+    }
+
+    /// The access token; fetched if there's none, or it's about to expire.
+    pub async fn token(&self, client: &Client) -> Result<Secret> {
+        let mut token = self.token.lock().await;
+        if let Some(token) = token.as_ref().filter(|token| token.is_fresh()) {
+            return Ok(token.access.clone());
+        }
+
+        let refresh = match token.as_ref() {
+            Some(token) => token.refresh.clone(),
+            None => self.refresh_token.clone(),
+        };
+        let fresh = match self.fetch(client, refresh.clone()).await {
+            // a refresh token the server gave may have expired; the credentials are enough
+            Err(_) if refresh.is_some() && self.refresh_token.is_none() => {
+                self.fetch(client, None).await?
+            }
+            fresh => fresh?,
+        };
+        let access = fresh.access.clone();
+        *token = Some(fresh);
+        Ok(access)
+    }
+
+    /// Drop the token, if it's still `stale`, i.e., after a `401`; the next request fetches one.
+    pub async fn invalidate(&self, stale: &Secret) {
+        let mut token = self.token.lock().await;
+        if let Some(token) = token.as_mut().filter(|token| &token.access == stale) {
+            token.expires_at = Some(Instant::now()); // the refresh token is kept
+        }
+    }
+
+    async fn fetch(&self, client: &Client, refresh: Option<Secret>) -> Result<Token> {
+        let scope = self.scopes.join(" ");
+        let mut form = vec![
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.expose()),
+        ];
+        match &refresh {
+            Some(refresh) => {
+                form.push(("grant_type", "refresh_token"));
+                form.push(("refresh_token", refresh.expose()));
+            }
+            None => form.push(("grant_type", "client_credentials")),
+        }
+        if !scope.is_empty() {
+            form.push(("scope", &scope));
+        }
+
+        let response = client.post(&self.token_url).form(&form).send().await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(Error::Status {
+                status,
+                url: self.token_url.clone(),
+                headers: Box::new(headers),
+                body,
+            });
+        }
+
+        let response: TokenResponse = decode::json("oauth2 token", body)?;
+        let expires_in = response.expires_in.map(Duration::from_secs);
+        Ok(Token {
+            access: Secret::new(response.access_token),
+            expires_at: expires_in.map(|expires_in| Instant::now() + expires_in),
+            refresh: response.refresh_token.map(Secret::new).or(refresh),
+        })
+    }
+}
+
+impl Token {
+    fn is_fresh(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => Instant::now() + OAuth2::MARGIN < expires_at,
+            None => true,
+        }
+    }
+}
+
+impl fmt::Debug for OAuth2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &self.client_secret)
+            .field("scopes", &self.scopes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A credential; `Debug` shows `***`, rather than its value.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+/// The environment variable `name`, for `env "NAME"` in `auth:`; the error names it.
+pub fn env(name: &str) -> std::result::Result<String, BoxError> {
+    std::env::var(name).map_err(|e| format!("`{name}`: {e}").into())
+}
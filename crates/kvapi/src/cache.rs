@@ -0,0 +1,293 @@
+use crate::{url::Url, Auth, Bytes};
+use async_trait::async_trait;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    ResponseBuilderExt, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// Responses of an API, kept for reuse, from `cache:` in `api!`, or `#[cache: 10m]` on a dict
+/// entry.
+///
+/// ```rust,ignore
+/// cache: 10m                                                   // every `GET`, in memory
+/// cache: { ttl: 1h, store: kvapi::cache::Disk::new(".cache") } // on disk
+///
+/// #[cache: 1d]
+/// "company_tickers.json": Tickers,                             // over the API's, if any
+/// ```
+///
+/// Only successful `GET`s are kept. A response younger than its ttl is served without a request;
+/// an older one is revalidated, by its `ETag` or `Last-Modified`, and served again if the server
+/// answers `304 Not Modified`. [`Response::cache`](crate::Response) tells which it was.
+///
+/// A response is kept by its URL, headers, and the API's credentials; APIs with other keys can
+/// share a store without serving each other's responses. Credentials a middleware adds aren't
+/// known to the cache, so such an API needs a store of its own.
+#[derive(Clone)]
+pub struct Cache {
+    store: Arc<dyn Store>,
+    ttl: Option<Duration>,
+}
+
+impl Cache {
+    pub fn new(store: impl Store) -> Self {
+        Self {
+            store: Arc::new(store),
+            ttl: None,
+        }
+    }
+
+    /// Keep every `GET` for `ttl`; otherwise, only those of entries with a `#[cache]`.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn store(&self) -> &dyn Store {
+        &*self.store
+    }
+
+    pub(crate) fn default_ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+}
+
+// up to 256 responses, in memory
+impl Default for Cache {
+    fn default() -> Self {
+        Self::new(Memory::new(256))
+    }
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where a response came from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// The server; kept, if its endpoint is cached.
+    #[default]
+    Miss,
+    /// The cache, without a request.
+    Hit,
+    /// The cache, once the server answered `304 Not Modified`.
+    Revalidated,
+}
+
+impl CacheStatus {
+    /// Served from the cache, i.e., a `Hit`, or `Revalidated`.
+    pub fn is_hit(&self) -> bool {
+        *self != Self::Miss
+    }
+}
+
+/// A cached response.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Stored {
+    pub status: u16,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub stored_at: SystemTime,
+    #[serde(skip)]
+    pub body: Bytes,
+}
+
+impl Stored {
+    pub(crate) async fn read(response: reqwest::Response) -> reqwest::Result<Self> {
+        let (status, url) = (response.status().as_u16(), response.url().to_string());
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        Ok(Self {
+            status,
+            url,
+            headers,
+            stored_at: SystemTime::now(),
+            body: response.bytes().await?,
+        })
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let mut headers = self.headers.iter();
+        let (_, value) = headers.find(|(key, _)| key.eq_ignore_ascii_case(name))?;
+        Some(value)
+    }
+
+    pub(crate) fn is_fresh(&self, ttl: Duration) -> bool {
+        self.stored_at.elapsed().is_ok_and(|age| age < ttl)
+    }
+
+    // the response, as if it were sent again
+    pub(crate) fn response(&self, cache: CacheStatus) -> reqwest::Response {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            if let (Ok(name), Ok(value)) =
+                (HeaderName::try_from(name), HeaderValue::try_from(value))
+            {
+                headers.append(name, value);
+            }
+        }
+        let mut response = http::Response::builder()
+            .status(StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK))
+            .extension(cache);
+        if let Ok(url) = Url::parse(&self.url) {
+            response = response.url(url);
+        }
+        let mut response = response
+            .body(self.body.clone())
+            .expect("a valid status & headers");
+        *response.headers_mut() = headers;
+        response.into()
+    }
+}
+
+// the key of a request: its URL, then a fingerprint of its headers & the API's credentials,
+// which are only added as it's sent
+pub(crate) fn key(request: &reqwest::Request, auth: Option<&Auth>) -> String {
+    let mut headers: Vec<(&str, &[u8])> = request
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_bytes()))
+        .collect();
+    headers.sort();
+
+    let mut hash = Sha256::new();
+    for (name, value) in headers {
+        hash.update(name.as_bytes());
+        hash.update([0]);
+        hash.update(value);
+        hash.update([0]);
+    }
+    hash.update([1]);
+    for part in auth.map(Auth::identity).unwrap_or_default() {
+        hash.update(part.as_bytes());
+        hash.update([0]);
+    }
+    let hash = hash.finalize();
+    let fingerprint: String = hash[..8].iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("{} {} {fingerprint}", request.method(), request.url())
+}
+
+/// Where cached responses are kept; any failure to read or write one is a miss.
+#[async_trait]
+pub trait Store: Send + Sync + 'static {
+    async fn get(&self, key: &str) -> Option<Stored>;
+    async fn put(&self, key: &str, stored: Stored);
+}
+
+/// The last `capacity` responses used, in memory.
+pub struct Memory {
+    capacity: usize,
+    lru: Mutex<Lru>,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<String, Stored>,
+    order: VecDeque<String>, // least recently used first
+}
+
+impl Memory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            lru: Mutex::default(),
+        }
+    }
+}
+
+impl Lru {
+    fn touch(&mut self, key: &str) {
+        if let Some(i) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(i).unwrap();
+            self.order.push_back(key);
+        }
+    }
+}
+
+#[async_trait]
+impl Store for Memory {
+    async fn get(&self, key: &str) -> Option<Stored> {
+        let mut lru = self.lru.lock().unwrap();
+        let stored = lru.entries.get(key).cloned()?;
+        lru.touch(key);
+        Some(stored)
+    }
+
+    async fn put(&self, key: &str, stored: Stored) {
+        let mut lru = self.lru.lock().unwrap();
+        match lru.entries.insert(key.to_string(), stored) {
+            Some(_) => lru.touch(key),
+            None => lru.order.push_back(key.to_string()),
+        }
+        while lru.order.len() > self.capacity {
+            let Some(oldest) = lru.order.pop_front() else {
+                break;
+            };
+            lru.entries.remove(&oldest);
+        }
+    }
+}
+
+/// Responses in files of `dir`, kept across runs; two per response, named by the SHA256 of
+/// its key: `{hash}.json`, its status & headers, and `{hash}.body`.
+pub struct Disk {
+    dir: PathBuf,
+}
+
+impl Disk {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn paths(&self, key: &str) -> (PathBuf, PathBuf) {
+        let hash = Sha256::digest(key.as_bytes());
+        let name: String = hash.iter().map(|byte| format!("{byte:02x}")).collect();
+        (
+            self.dir.join(format!("{name}.json")),
+            self.dir.join(format!("{name}.body")),
+        )
+    }
+}
+
+#[async_trait]
+impl Store for Disk {
+    async fn get(&self, key: &str) -> Option<Stored> {
+        let (meta, body) = self.paths(key);
+        let meta = tokio::fs::read(meta).await.ok()?;
+        let mut stored: Stored = serde_json::from_slice(&meta).ok()?;
+        stored.body = tokio::fs::read(body).await.ok()?.into();
+        Some(stored)
+    }
+
+    // the body first; a response without its `.json` isn't read
+    async fn put(&self, key: &str, stored: Stored) {
+        let (meta, body) = self.paths(key);
+        let Ok(json) = serde_json::to_vec(&stored) else {
+            return;
+        };
+        if tokio::fs::create_dir_all(&self.dir).await.is_err() {
+            return;
+        }
+        let _ = tokio::fs::remove_file(&meta).await;
+        if tokio::fs::write(body, &stored.body).await.is_ok() {
+            let _ = tokio::fs::write(meta, json).await;
+        }
+    }
+}
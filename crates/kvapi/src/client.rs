@@ -0,0 +1,540 @@
+use crate::{
+    cache::{self, CacheStatus, Stored},
+    error::{self, ApiError, ErrorDecoder},
+    url::Url,
+    Auth, Bytes, Cache, Envelope, Error, Format, Middleware, RateLimit, Response, Result, Retry,
+};
+use reqwest::{
+    header::{HeaderMap, IF_MODIFIED_SINCE, IF_NONE_MATCH},
+    Client, Method, RequestBuilder, ResponseBuilderExt, StatusCode,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// What every endpoint of a generated API shares: one HTTP client, and the API's headers.
+///
+/// The root struct (i.e., `Fred`) creates it once, and each endpoint borrows it through an
+/// `Arc`; an API with 40 endpoints still has a single connection pool.
+///
+/// ```rust,ignore
+/// // one pool, shared by several APIs
+/// let client = kvapi::Client::new();
+/// let fred = Fred::with_client(client.clone());
+/// let sec = Sec::with_client(client);
+/// ```
+///
+/// The `headers:` of an API are sent with each request, rather than set on the client, so
+/// they're kept when a client is given; so are its middleware, rate limit, retry policy,
+/// timeout & cache.
+#[derive(Clone, Debug, Default)]
+pub struct Api {
+    client: Client,
+    headers: HeaderMap,
+    error: Option<ErrorDecoder>,
+    envelope: Option<Envelope>,
+    auth: Option<Auth>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    rate_limit: Option<RateLimit>,
+    retry: Option<Retry>,
+    timeout: Option<Duration>,
+    cache: Cache,
+}
+
+/// A request to send through an [`Api`], with the settings of its endpoint in `api!`: its
+/// `#[weight]` of the rate limit, and its own `#[retry]` policy, `#[timeout]` & `#[cache]`
+/// ttl, if any.
+///
+/// Any `RequestBuilder` is a call of weight 1, retried, timed out & cached by the API's
+/// settings.
+#[derive(Debug)]
+pub struct Call {
+    request: RequestBuilder,
+    weight: u32,
+    retry: Option<Retry>,
+    timeout: Option<Duration>,
+    cache: Option<Duration>,
+}
+
+impl Call {
+    pub fn new(request: RequestBuilder) -> Self {
+        Self {
+            request,
+            weight: 1,
+            retry: None,
+            timeout: None,
+            cache: None,
+        }
+    }
+
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Retry by `retry`, rather than the API's policy.
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Time out each attempt after `timeout`, rather than the API's.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Keep a successful `GET` for `ttl`, rather than the API's; a zero `ttl` doesn't.
+    pub fn cache(mut self, ttl: Duration) -> Self {
+        self.cache = Some(ttl);
+        self
+    }
+
+    pub(crate) fn url(&self) -> Result<Url> {
+        Ok(self.copy()?.build()?.url().clone())
+    }
+
+    // the same call, sent to `url`; i.e., the next page
+    pub(crate) fn to(&self, url: Url) -> Result<Self> {
+        let (client, request) = self.copy()?.build_split();
+        let mut request = request?;
+        *request.url_mut() = url;
+        Ok(Self {
+            request: RequestBuilder::from_parts(client, request),
+            ..self.again()?
+        })
+    }
+
+    // the same call, to send again; i.e., the next poll
+    pub(crate) fn again(&self) -> Result<Self> {
+        Ok(Self {
+            request: self.copy()?,
+            weight: self.weight,
+            retry: self.retry.clone(),
+            timeout: self.timeout,
+            cache: self.cache,
+        })
+    }
+
+    fn copy(&self) -> Result<RequestBuilder> {
+        let request = self.request.try_clone();
+        request.ok_or_else(|| Error::Url("a request with a streamed body can't be copied".into()))
+    }
+}
+
+impl From<RequestBuilder> for Call {
+    fn from(request: RequestBuilder) -> Self {
+        Self::new(request)
+    }
+}
+
+impl Api {
+    pub fn new(client: Client, headers: HeaderMap) -> Self {
+        Self {
+            client,
+            headers,
+            error: None,
+            envelope: None,
+            auth: None,
+            middleware: vec![],
+            rate_limit: None,
+            retry: None,
+            timeout: None,
+            cache: Cache::default(),
+        }
+    }
+
+    /// Decode the body of any non-2xx response into `T`, returned as an `Error::Api`;
+    /// a body that isn't a `T` is kept as an `Error::Status`.
+    pub fn with_error<T>(mut self) -> Self
+    where
+        T: DeserializeOwned + Debug + Send + Sync + 'static,
+    {
+        self.error = Some(error::decoder::<T>());
+        self
+    }
+
+    /// Open the `envelope` of every response, unless an endpoint has its own.
+    pub fn with_envelope(mut self, envelope: Envelope) -> Self {
+        self.envelope = Some(envelope);
+        self
+    }
+
+    /// Send the credentials of `auth` with every request.
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Run `middleware` around every request, after any already added; but before any signer,
+    /// so that it signs the request as it's sent.
+    pub fn with_middleware(mut self, middleware: impl Middleware) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        // a stable sort; each keeps its order among the others, or the signers
+        self.middleware.sort_by_key(|middleware| middleware.is_signer());
+        self
+    }
+
+    /// Take the weight of every request from `rate_limit`; clones of it share the one bucket.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Retry every request by `retry`, unless its endpoint has its own policy.
+    pub fn with_retry(mut self, retry: Retry) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Time out every request after `timeout`, from its start to the end of its response,
+    /// unless its endpoint has its own; a timeout is an `Error::Timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Keep responses in `cache`; every endpoint's, if it has a ttl, or else those with a
+    /// `#[cache]`. By default, they're kept in memory.
+    pub fn with_cache(mut self, cache: Cache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Headers of every request, from `headers:` in `api!`.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The rate limit of every request, from `rate_limit:` in `api!`.
+    pub fn rate_limit(&self) -> Option<&RateLimit> {
+        self.rate_limit.as_ref()
+    }
+
+    /// Wait for the rate limit, if any, to allow a request of `weight`.
+    pub async fn acquire(&self, weight: u32) {
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.acquire(weight).await;
+        }
+    }
+
+    /// Send a request, through the middleware, and return its response as is, whatever its status.
+    ///
+    /// A `GET` of a cached endpoint is served from the cache while it's fresh, and revalidated
+    /// after; it's kept by its URL, headers & the API's credentials. A failure is retried by the
+    /// request's policy, or else the API's; each attempt waits for the rate limit, and times out
+    /// by the request's timeout, or else the API's. With OAuth2, a request answered with a `401`
+    /// is sent again, once, with a new token.
+    pub async fn execute(&self, request: impl Into<Call>) -> Result<reqwest::Response> {
+        let Call {
+            request,
+            weight,
+            retry,
+            timeout,
+            cache,
+        } = request.into();
+        let (client, request) = request.build_split();
+        let mut request = request.map_err(|e| self.redact_error(e))?;
+        if let Some(timeout) = timeout.or(self.timeout) {
+            *request.timeout_mut() = Some(timeout);
+        }
+        let retry = retry.as_ref().or(self.retry.as_ref());
+
+        let ttl = cache.or(self.cache.default_ttl());
+        let Some(ttl) = ttl.filter(|ttl| !ttl.is_zero() && request.method() == Method::GET) else {
+            let response = self.attempts(client, request, weight, retry).await?;
+            return Ok(self.redact_response(response));
+        };
+
+        // a fresh response isn't sent; a stale one is, if it's modified
+        let key = cache::key(&request, self.auth.as_ref());
+        let stored = self.cache.store().get(&key).await;
+        if let Some(stored) = &stored {
+            if stored.is_fresh(ttl) {
+                return Ok(stored.response(CacheStatus::Hit));
+            }
+            let headers = request.headers_mut();
+            let etag = stored.header("etag").and_then(|etag| etag.parse().ok());
+            if let Some(etag) = etag {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+            let modified = stored.header("last-modified");
+            if let Some(modified) = modified.and_then(|modified| modified.parse().ok()) {
+                headers.insert(IF_MODIFIED_SINCE, modified);
+            }
+        }
+
+        let response = self.attempts(client, request, weight, retry).await?;
+        let response = self.redact_response(response);
+        match (response.status(), stored) {
+            (StatusCode::NOT_MODIFIED, Some(mut stored)) => {
+                stored.stored_at = SystemTime::now();
+                self.cache.store().put(&key, stored.clone()).await;
+                Ok(stored.response(CacheStatus::Revalidated))
+            }
+            (status, _) if status.is_success() => {
+                let stored = Stored::read(response).await;
+                let stored = stored.map_err(|e| self.redact_error(e))?;
+                self.cache.store().put(&key, stored.clone()).await;
+                Ok(stored.response(CacheStatus::Miss))
+            }
+            _ => Ok(response),
+        }
+    }
+
+    // send a request, as often as `retry` allows
+    async fn attempts(
+        &self,
+        client: Client,
+        request: reqwest::Request,
+        weight: u32,
+        retry: Option<&Retry>,
+    ) -> Result<reqwest::Response> {
+        let Some(retry) = retry.filter(|retry| retry.allows(request.method())) else {
+            let request = RequestBuilder::from_parts(client, request);
+            return self.authorize(request, weight).await;
+        };
+        let mut attempt = 1;
+        loop {
+            // a streamed body can only be sent once
+            let Some(next) = request.try_clone() else {
+                let request = RequestBuilder::from_parts(client, request);
+                return self.authorize(request, weight).await;
+            };
+            let next = RequestBuilder::from_parts(client.clone(), next);
+            let result = self.authorize(next, weight).await;
+            match retry.delay(attempt, &result) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    // send a request, with an OAuth2 token, if any; renewed once after a `401`
+    async fn authorize(&self, request: RequestBuilder, weight: u32) -> Result<reqwest::Response> {
+        let Some(Auth::OAuth2(oauth2)) = &self.auth else {
+            return self.dispatch(request, weight).await;
+        };
+
+        let retry = request.try_clone(); // none for a streamed body
+        let token = oauth2.token(&self.client).await?;
+        let request = request.bearer_auth(token.expose());
+        let response = self.dispatch(request, weight).await?;
+        let (StatusCode::UNAUTHORIZED, Some(retry)) = (response.status(), retry) else {
+            return Ok(response);
+        };
+
+        oauth2.invalidate(&token).await;
+        let token = oauth2.token(&self.client).await?;
+        let retry = retry.bearer_auth(token.expose());
+        self.dispatch(retry, weight).await
+    }
+
+    // send a request, once the rate limit allows it, with the API's credentials, through the
+    // middleware
+    async fn dispatch(&self, request: RequestBuilder, weight: u32) -> Result<reqwest::Response> {
+        self.acquire(weight).await;
+        let request = match &self.auth {
+            Some(auth) => auth.apply(request),
+            None => request,
+        };
+        if self.middleware.is_empty() {
+            return request.send().await.map_err(|e| self.redact_error(e));
+        }
+
+        let (client, request) = request.build_split();
+        let mut request = request.map_err(|e| self.redact_error(e))?;
+        for middleware in &self.middleware {
+            middleware.before(&mut request).await?;
+        }
+        let result = client.execute(request).await;
+        let mut response = result.map_err(|e| self.redact_error(e))?;
+        for middleware in self.middleware.iter().rev() {
+            response = middleware.after(response).await?;
+        }
+        Ok(response)
+    }
+
+    /// Send a request; a non-2xx status is an `Error::Api` or an `Error::Status`.
+    pub async fn send(&self, request: impl Into<Call>) -> Result<reqwest::Response> {
+        let response = self.execute(request).await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let url = self.redact(response.url()).to_string();
+        let headers = response.headers().clone();
+        let body = response.text().await.unwrap_or_default();
+        if let Some(error) = self.api_error(&body, status, &url, &headers) {
+            return Err(Error::Api(error));
+        }
+        Err(Error::Status {
+            status,
+            url,
+            headers: Box::new(headers),
+            body,
+        })
+    }
+
+    /// Send a request, and return its body, undecoded.
+    pub async fn bytes(&self, request: impl Into<Call>) -> Result<Bytes> {
+        let response = self.send(request).await?;
+        response.bytes().await.map_err(|e| self.redact_error(e))
+    }
+
+    /// Send a request, and return its body as text, undecoded.
+    pub async fn text(&self, request: impl Into<Call>) -> Result<String> {
+        let response = self.send(request).await?;
+        response.text().await.map_err(|e| self.redact_error(e))
+    }
+
+    /// Send a request, and deserialize its response as `format`; `endpoint` names it in any error.
+    ///
+    /// The data of a JSON response is taken out of the `envelope` of the endpoint, or else of the
+    /// API, if any.
+    pub async fn decode<T>(
+        &self,
+        endpoint: &'static str,
+        format: Format,
+        envelope: Option<&Envelope>,
+        request: impl Into<Call>,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self.decode_with_meta::<T>(endpoint, format, envelope, request);
+        Ok(response.await?.into_inner())
+    }
+
+    /// `decode()`, keeping the status, headers & timing of the response.
+    pub async fn decode_with_meta<T>(
+        &self,
+        endpoint: &'static str,
+        format: Format,
+        envelope: Option<&Envelope>,
+        request: impl Into<Call>,
+    ) -> Result<Response<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self.text_with_meta(request).await?;
+        self.open(endpoint, format, envelope, response)
+    }
+
+    // send a request, and read its body as text, with the status, headers & timing
+    pub(crate) async fn text_with_meta(&self, request: impl Into<Call>) -> Result<Response<String>> {
+        let (sent_at, start) = (SystemTime::now(), Instant::now());
+        let response = self.send(request).await?;
+        let (status, url) = (response.status(), self.redact(response.url()));
+        let headers = response.headers().clone();
+        let cache = response.extensions().get().copied().unwrap_or_default();
+        let body = response.text().await.map_err(|e| self.redact_error(e))?;
+        Ok(Response {
+            data: body,
+            status,
+            headers,
+            url,
+            elapsed: start.elapsed(),
+            sent_at,
+            cache,
+        })
+    }
+
+    // decode the body of a response as `format`, out of its envelope, if any
+    pub(crate) fn open<T>(
+        &self,
+        endpoint: &'static str,
+        format: Format,
+        envelope: Option<&Envelope>,
+        response: Response<String>,
+    ) -> Result<Response<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let Response {
+            data: body,
+            status,
+            headers,
+            url,
+            elapsed,
+            sent_at,
+            cache,
+        } = response;
+        let envelope = envelope.or(self.envelope.as_ref());
+        let data = match envelope.filter(|_| format == Format::Json) {
+            None => format.decode(endpoint, body)?,
+            Some(envelope) => envelope.open(endpoint, body, |error, body| {
+                let error = self.api_error(&error.to_string(), status, url.as_str(), &headers)?;
+                Some(Error::Api(ApiError {
+                    body: body.to_string(),
+                    ..error
+                }))
+            })?,
+        };
+        Ok(Response {
+            data,
+            status,
+            headers,
+            url,
+            elapsed,
+            sent_at,
+            cache,
+        })
+    }
+
+    // the URL, without any API key in its query
+    fn redact(&self, url: &Url) -> Url {
+        let mut url = url.clone();
+        if let Some(auth) = &self.auth {
+            auth.redact(&mut url);
+        }
+        url
+    }
+
+    // the response, with any key in the query of its URL redacted; as is, otherwise
+    fn redact_response(&self, response: reqwest::Response) -> reqwest::Response {
+        let url = self.redact(response.url());
+        if url == *response.url() {
+            return response;
+        }
+        let (mut parts, body) = http::Response::from(response).into_parts();
+        let redacted = http::Response::builder().url(url).body(());
+        let (redacted, _) = redacted.expect("a URL").into_parts();
+        parts.extensions.extend(redacted.extensions);
+        http::Response::from_parts(parts, body).into()
+    }
+
+    fn redact_error(&self, mut e: reqwest::Error) -> Error {
+        if let (Some(auth), Some(url)) = (&self.auth, e.url_mut()) {
+            auth.redact(url);
+        }
+        Error::from(e)
+    }
+
+    // the `error: Type` of the API, decoded from `json`
+    fn api_error(
+        &self,
+        json: &str,
+        status: StatusCode,
+        url: &str,
+        headers: &HeaderMap,
+    ) -> Option<ApiError> {
+        let error = (self.error?)(json)?;
+        Some(ApiError {
+            status,
+            url: url.to_string(),
+            headers: Box::new(headers.clone()),
+            body: json.to_string(),
+            error,
+        })
+    }
+}
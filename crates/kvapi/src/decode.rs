@@ -0,0 +1,111 @@
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "csv")]
+mod rows;
+
+/// Format of a response body, picked by the extension of the dict key, i.e., `"rates.csv"`,
+/// or by `#[format: csv]`; `json` otherwise.
+///
+/// Each format, besides `json`, is behind the cargo feature of the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    #[default]
+    Json,
+    /// Rows, with a header; the type is a collection of them, i.e., `Vec<Row>`.
+    #[cfg(feature = "csv")]
+    Csv,
+    #[cfg(feature = "xml")]
+    Xml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl Format {
+    /// Deserialize the `body` of `endpoint`'s response, keeping the path to any error.
+    pub fn decode<T>(self, endpoint: &'static str, body: String) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            Self::Json => json(endpoint, body),
+            #[cfg(feature = "csv")]
+            Self::Csv => csv(endpoint, body),
+            #[cfg(feature = "xml")]
+            Self::Xml => xml(endpoint, body),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => yaml(endpoint, body),
+            #[cfg(feature = "toml")]
+            Self::Toml => toml(endpoint, body),
+        }
+    }
+}
+
+/// Deserialize the JSON `body` of `endpoint`'s response, keeping the path to any error.
+///
+/// ```rust
+/// let error = kvapi::decode::json::<Vec<u32>>("numbers", r#"[1, 2, "three"]"#.into()).unwrap_err();
+/// assert!(error.to_string().contains("`numbers` at `[2]`"));
+/// ```
+pub fn json<T>(endpoint: &'static str, body: String) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let deserializer = &mut serde_json::Deserializer::from_str(&body);
+    let result = serde_path_to_error::deserialize(deserializer);
+    result.map_err(|e| error(endpoint, e, body))
+}
+
+/// Deserialize the CSV `body` of `endpoint`'s response; each row is deserialized by its header.
+#[cfg(feature = "csv")]
+pub fn csv<T>(endpoint: &'static str, body: String) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let rows = match rows::Rows::read(&body) {
+        Ok(rows) => rows,
+        Err(e) => return Err(Error::decode(endpoint, String::new(), e, body)),
+    };
+    let result = serde_path_to_error::deserialize(rows);
+    result.map_err(|e| error(endpoint, e, body))
+}
+
+#[cfg(feature = "xml")]
+pub fn xml<T>(endpoint: &'static str, body: String) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let deserializer = &mut quick_xml::de::Deserializer::from_str(&body);
+    let result = serde_path_to_error::deserialize(deserializer);
+    result.map_err(|e| error(endpoint, e, body))
+}
+
+#[cfg(feature = "yaml")]
+pub fn yaml<T>(endpoint: &'static str, body: String) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let deserializer = serde_yaml::Deserializer::from_str(&body);
+    let result = serde_path_to_error::deserialize(deserializer);
+    result.map_err(|e| error(endpoint, e, body))
+}
+
+#[cfg(feature = "toml")]
+pub fn toml<T>(endpoint: &'static str, body: String) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let deserializer = ::toml::Deserializer::new(&body);
+    let result = serde_path_to_error::deserialize(deserializer);
+    result.map_err(|e| error(endpoint, e, body))
+}
+
+fn error<E>(endpoint: &'static str, e: serde_path_to_error::Error<E>, body: String) -> Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    Error::decode(endpoint, e.path().to_string(), e.into_inner(), body)
+}
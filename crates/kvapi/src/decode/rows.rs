@@ -0,0 +1,200 @@
+// A `Deserializer` over the rows of a CSV body.
+//
+// `csv` can only deserialize one record at a time, into a known type; this presents the whole
+// body as a sequence instead, so the endpoint's type is any collection of rows, i.e., `Vec<Row>`,
+// and `serde_path_to_error` can point at the failing row & column, i.e., `[3].value`.
+//
+// Each row is a map of header -> field, or a sequence of fields for a tuple; fields are parsed
+// into whatever type is asked for, and an empty field is a `None`. A row with more or fewer
+// fields than the header is an error at that row, i.e., `[3]`.
+
+use serde::de::{
+    self,
+    value::{Error, MapDeserializer, SeqDeserializer},
+    IntoDeserializer, Unexpected, Visitor,
+};
+use std::rc::Rc;
+
+pub(super) struct Rows {
+    headers: Rc<[String]>,
+    records: Vec<Vec<String>>,
+}
+
+impl Rows {
+    pub(super) fn read(body: &str) -> Result<Self, csv::Error> {
+        // the length of each row is checked as it's deserialized, to point at it
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(body.as_bytes());
+        let headers = reader.headers()?.iter().map(String::from).collect();
+        let records = reader
+            .records()
+            .map(|record| Ok(record?.iter().map(String::from).collect()))
+            .collect::<Result<_, csv::Error>>()?;
+        Ok(Self { headers, records })
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Rows {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let headers = self.headers;
+        let rows = self.records.into_iter().map(|fields| Row {
+            headers: headers.clone(),
+            fields,
+        });
+        SeqDeserializer::new(rows).deserialize_any(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+struct Row {
+    headers: Rc<[String]>,
+    fields: Vec<String>,
+}
+
+impl Row {
+    // a field for each header; none missing, or left over
+    fn check(&self) -> Result<(), Error> {
+        if self.fields.len() == self.headers.len() {
+            return Ok(());
+        }
+        let expected = format!("{} fields, one per header", self.headers.len());
+        let expected: &str = &expected;
+        Err(de::Error::invalid_length(self.fields.len(), &expected))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Row {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Row {
+    type Error = Error;
+
+    // header -> field
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.check()?;
+        let fields = self.fields.into_iter().map(Field);
+        let entries = self.headers.iter().cloned().zip(fields);
+        MapDeserializer::new(entries).deserialize_any(visitor)
+    }
+
+    // fields, in order
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.check()?;
+        SeqDeserializer::new(self.fields.into_iter().map(Field)).deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct map struct enum identifier ignored_any
+    }
+}
+
+struct Field(String);
+
+impl<'de> IntoDeserializer<'de, Error> for Field {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+// parse the field as the primitive that's asked for
+macro_rules! parse {
+    ($($deserialize:ident => $visit:ident,)*) => {
+        $(
+            fn $deserialize<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0.trim().parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Field {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+
+    parse! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // unit variants, by name
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let field: de::value::StringDeserializer<Error> = self.0.into_deserializer();
+        field.deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{decode, Error, Result};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Where the data is, in a response that wraps it, and how the wrapper reports an error.
+///
+/// ```text
+/// Kraken  ->  {"error": [], "result": {...}}          envelope: { data: "result", error: "error" }
+/// KuCoin  ->  {"code": "200000", "data": {...}}       envelope: { data: "data", success: "code" == "200000" }
+/// FRED    ->  {"count": 45, "observations": [...]}    #[envelope(data: "observations")]
+/// ```
+///
+/// The envelope is opened before decoding, so an endpoint's type is only the data within it.
+/// A failure reported by the envelope is an error, even with an HTTP 200; it's decoded into the
+/// API's `error:` type if possible, or kept as an `Error::Envelope`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Envelope {
+    data: &'static str, // `/`-separated, for nested data, i.e., `"data/items"`
+    check: Option<Check>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Check {
+    // the field is set, and not empty, i.e., `"error": ["EQuery:Unknown asset pair"]`
+    Error(&'static str),
+    // the field is anything but the value, i.e., `"code": "400100"`
+    Success(&'static str, Value),
+}
+
+impl Envelope {
+    pub fn new(data: &'static str) -> Self {
+        Self { data, check: None }
+    }
+
+    /// Fail if `field` is set, and not `null`, `false`, or empty.
+    pub fn error(mut self, field: &'static str) -> Self {
+        self.check = Some(Check::Error(field));
+        self
+    }
+
+    /// Fail unless `field` is `value`.
+    pub fn success(mut self, field: &'static str, value: impl Into<Value>) -> Self {
+        self.check = Some(Check::Success(field, value.into()));
+        self
+    }
+
+    /// Decode the data of the response `body` as a `T`, unless the envelope reports an error.
+    ///
+    /// The error is the envelope, less its data; `api_error` decodes it into the API's own type.
+    pub fn open<T>(
+        &self,
+        endpoint: &'static str,
+        body: String,
+        api_error: impl FnOnce(&Value, &str) -> Option<Error>,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let mut envelope: Value = decode::json(endpoint, body.clone())?;
+
+        let pointer = format!("/{}", self.data);
+        let data = envelope.pointer_mut(&pointer).map(Value::take);
+        if self.is_error(&envelope) {
+            remove(&mut envelope, self.data);
+            return Err(api_error(&envelope, &body).unwrap_or(Error::Envelope {
+                endpoint,
+                error: envelope,
+                body,
+            }));
+        }
+
+        let Some(data) = data else {
+            let error = format!("missing field `{}`", self.data);
+            return Err(Error::decode(
+                endpoint,
+                self.data.replace('/', "."),
+                error,
+                body,
+            ));
+        };
+        serde_path_to_error::deserialize(data).map_err(|e| {
+            let data = self.data.replace('/', ".");
+            let path = match e.path().to_string() {
+                path if path == "." => data,
+                path if path.starts_with('[') => format!("{data}{path}"),
+                path => format!("{data}.{path}"),
+            };
+            Error::decode(endpoint, path, e.into_inner(), body)
+        })
+    }
+
+    fn is_error(&self, envelope: &Value) -> bool {
+        match &self.check {
+            Some(Check::Error(field)) => match envelope.get(field) {
+                None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+                Some(Value::String(s)) => !s.is_empty(),
+                Some(Value::Array(a)) => !a.is_empty(),
+                Some(Value::Object(o)) => !o.is_empty(),
+                Some(_) => true,
+            },
+            Some(Check::Success(field, value)) => envelope.get(field) != Some(value),
+            None => false,
+        }
+    }
+}
+
+// remove the (possibly nested) data from the envelope
+fn remove(envelope: &mut Value, data: &str) {
+    let (parent, key) = match data.rsplit_once('/') {
+        Some((parent, key)) => (envelope.pointer_mut(&format!("/{parent}")), key),
+        None => (Some(envelope), data),
+    };
+    if let Some(Value::Object(parent)) = parent {
+        parent.remove(key);
+    }
+}
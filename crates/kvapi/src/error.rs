@@ -0,0 +1,281 @@
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::{any::Any, fmt, time::Duration};
+
+/// `Result` of every fallible `kvapi` call.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Everything that can go wrong when building an API, or sending a request to it.
+///
+/// ```rust,ignore
+/// match fred.series.observations.get().await {
+///     Ok(observations) => { ... }
+///     Err(e) if e.is_rate_limited() => { /* back off */ }
+///     Err(e) if e.is_timeout() => { /* try again, or give it more time */ }
+///     Err(e) if e.is_decode() => { /* the schema changed */ }
+///     Err(e) => return Err(e.into()),
+/// }
+/// ```
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The request couldn't be sent, or its response couldn't be read, i.e., a refused connection.
+    #[error("request failed: {0}")]
+    Transport(reqwest::Error),
+
+    /// The request, its connection, or its response, took longer than the `timeout:` allows.
+    #[error("request timed out: {0}")]
+    Timeout(reqwest::Error),
+
+    /// The server answered with a non-2xx status, and its own error, i.e., `error: BinanceError`.
+    #[error("{0}")]
+    Api(ApiError),
+
+    /// The server answered with a non-2xx status; the body is kept as is.
+    #[error("HTTP {status} from `{url}`: {}", snippet(body))]
+    Status {
+        status: StatusCode,
+        url: String,
+        headers: Box<HeaderMap>, // boxed to keep `Result`s small
+        body: String,
+    },
+
+    /// The response body didn't match the endpoint's type.
+    #[error(
+        "failed to decode the response of `{endpoint}` at `{path}`: {source}; body: {}",
+        snippet(body)
+    )]
+    Decode {
+        endpoint: &'static str, // the dict key, i.e., `"series/{series_id}/observations"`
+        path: String,           // where it failed, i.e., `observations[3].value`
+        source: BoxError,
+        body: String,
+    },
+
+    /// The response's envelope reported an error, i.e., `{"error": ["EQuery:Unknown asset pair"]}`;
+    /// `error` is the envelope, less its data.
+    #[error("`{endpoint}` reported an error: {error}")]
+    Envelope {
+        endpoint: &'static str,
+        error: Value,
+        body: String,
+    },
+
+    /// The URL, or its query, couldn't be built.
+    #[error("{0}")]
+    Url(String),
+
+    /// A WebSocket connection failed, or went quiet; it's opened again.
+    #[error("websocket failed: {0}")]
+    WebSocket(BoxError),
+
+    /// A middleware failed the request, i.e., a signer without its secret.
+    #[error("middleware failed: {0}")]
+    Middleware(BoxError),
+
+    /// The credentials of `auth:` couldn't be evaluated when building the API, i.e., an unset
+    /// environment variable.
+    #[error("failed to build `{api}`: invalid auth: {source}")]
+    Auth { api: &'static str, source: BoxError },
+
+    /// A header of `headers:` couldn't be evaluated when building the API.
+    #[error("failed to build `{api}`: invalid header `{name}`: {source}")]
+    Header {
+        api: &'static str,
+        name: &'static str,
+        source: BoxError,
+    },
+}
+
+impl Error {
+    pub(crate) fn decode(
+        endpoint: &'static str,
+        path: String,
+        source: impl Into<BoxError>,
+        body: String,
+    ) -> Self {
+        Self::Decode {
+            endpoint,
+            path,
+            source: source.into(),
+            body,
+        }
+    }
+
+    #[cfg(feature = "ws")]
+    pub(crate) fn websocket(source: impl Into<BoxError>) -> Self {
+        Self::WebSocket(source.into())
+    }
+
+    /// A middleware's own error; `source` is any error, or message.
+    pub fn middleware(source: impl Into<BoxError>) -> Self {
+        Self::Middleware(source.into())
+    }
+
+    /// Whether the same request could succeed if sent again, i.e., a timeout, a `429`, or a `503`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Timeout(_) => true,
+            Self::Transport(e) => e.is_connect() || e.is_body(),
+            _ => matches!(
+                self.status().map(|status| status.as_u16()),
+                Some(408 | 429 | 500 | 502..=504)
+            ),
+        }
+    }
+
+    /// `429 Too Many Requests`.
+    pub fn is_rate_limited(&self) -> bool {
+        self.status() == Some(StatusCode::TOO_MANY_REQUESTS)
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout(_))
+    }
+
+    /// The response didn't match its type; most likely, the API changed.
+    pub fn is_decode(&self) -> bool {
+        matches!(self, Self::Decode { .. })
+    }
+
+    /// Status of a non-2xx response.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Status { status, .. } => Some(*status),
+            Self::Api(e) => Some(e.status),
+            Self::Transport(e) | Self::Timeout(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    /// How long a non-2xx response asked to wait, by its `Retry-After` header.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::Status { headers, .. } => crate::retry::retry_after(headers),
+            Self::Api(e) => crate::retry::retry_after(&e.headers),
+            _ => None,
+        }
+    }
+
+    /// Body of a non-2xx, or undecodable, response.
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            Self::Status { body, .. } | Self::Decode { body, .. } | Self::Envelope { body, .. } => {
+                Some(body)
+            }
+            Self::Api(e) => Some(&e.body),
+            _ => None,
+        }
+    }
+
+    /// The API's own error, if it's a `T`, i.e., `e.api::<BinanceError>()`.
+    pub fn api<T: 'static>(&self) -> Option<&T> {
+        match self {
+            Self::Api(e) => e.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+// a timeout, wherever it happens, is its own kind of error
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        match e.is_timeout() {
+            true => Self::Timeout(e),
+            false => Self::Transport(e),
+        }
+    }
+}
+
+/// The error body of a non-2xx response, decoded into the API's `error: Type`.
+///
+/// ```rust,ignore
+/// if let Err(kvapi::Error::Api(e)) = binance.ticker.price.request().symbol("BNB").get().await {
+///     let error: &BinanceError = e.downcast_ref().unwrap();
+///     println!("{} {}", error.code, error.msg); // -1121 Invalid symbol.
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub url: String,
+    pub headers: Box<HeaderMap>,
+    pub body: String, // as received
+    pub(crate) error: Box<dyn Body>,
+}
+
+impl ApiError {
+    pub fn new<T>(
+        status: StatusCode,
+        url: String,
+        headers: HeaderMap,
+        body: String,
+        error: T,
+    ) -> Self
+    where
+        T: fmt::Debug + Send + Sync + 'static,
+    {
+        Self {
+            status,
+            url,
+            headers: Box::new(headers),
+            body,
+            error: Box::new(error),
+        }
+    }
+
+    // `*` to downcast the error, not its `Box`
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        (*self.error).as_any().downcast_ref()
+    }
+
+    pub fn is<T: 'static>(&self) -> bool {
+        (*self.error).as_any().is::<T>()
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "HTTP {} from `{}`: {:?}",
+            self.status, self.url, self.error
+        )
+    }
+}
+
+// decodes JSON into the `error: Type` of an API
+pub(crate) type ErrorDecoder = fn(&str) -> Option<Box<dyn Body>>;
+
+pub(crate) fn decoder<T>() -> ErrorDecoder
+where
+    T: DeserializeOwned + fmt::Debug + Send + Sync + 'static,
+{
+    |json| {
+        let error = serde_json::from_str::<T>(json).ok()?;
+        Some(Box::new(error))
+    }
+}
+
+// any `error: Type`; `Debug` for messages, `Any` for downcasting
+pub(crate) trait Body: Any + fmt::Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + fmt::Debug + Send + Sync> Body for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// the start of a body, for messages; bodies can be huge
+fn snippet(body: &str) -> String {
+    const MAX: usize = 200;
+    match body.char_indices().nth(MAX) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
@@ -1,8 +1,50 @@
+pub mod auth;
+pub mod cache;
+pub mod client;
+pub mod decode;
+pub mod envelope;
+pub mod error;
+pub mod many;
+pub mod middleware;
+pub mod paginate;
+pub mod query;
+pub mod rate_limit;
+pub mod response;
+pub mod retry;
+pub mod signer;
+pub mod url;
+pub mod watch;
+#[cfg(feature = "ws")]
+pub mod ws;
+
 // Re-exports
-pub use anyhow::Result;
+pub use async_trait::async_trait;
+pub use auth::{Auth, OAuth2};
+pub use bytes::Bytes;
+pub use cache::Cache;
+pub use client::{Api, Call};
+pub use decode::Format;
+pub use envelope::Envelope;
+pub use error::{Error, Result};
+pub use futures; // for `StreamExt`, to read the streams of `pages()`, `items()`, `watch()` & `subscribe()`
+pub use futures::Stream;
 pub use kvapi_macros::api;
+pub use many::Many;
+pub use middleware::Middleware;
+pub use paginate::Paginate;
+pub use query::Query;
+pub use rate_limit::RateLimit;
+pub use reqwest; // for the raw `reqwest::Response` of `get_response()`
 pub use reqwest::{
     header::{HeaderMap, HeaderValue},
-    Client, ClientBuilder,
+    Client, ClientBuilder, Method, Request, RequestBuilder, StatusCode,
 };
+pub use response::Response;
+pub use retry::Retry;
+pub use serde::de::DeserializeOwned;
 pub use serde_json::Value;
+pub use signer::Signer;
+pub use tokio_util::sync::CancellationToken; // to end a `watch()`, or `subscribe()`
+pub use watch::Watch;
+#[cfg(feature = "ws")]
+pub use ws::{Socket, Subscription};
@@ -0,0 +1,115 @@
+use crate::{Api, Call, Envelope, Format, Result};
+use futures::{future::BoxFuture, stream, FutureExt, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::{future::IntoFuture, marker::PhantomData};
+
+/// The same endpoint, requested once per item, from its `get_many()`; `concurrency` at a time.
+///
+/// ```rust,ignore
+/// // by the path & required query parameters of the entry, i.e., `#[params(symbol: &str)]`
+/// let prices = binance.ticker.price.get_many(["BTCUSDT", "ETHUSDT"]).concurrency(16).await;
+///
+/// // as each is done, by the index of its item
+/// let mut prices = binance.ticker.price.get_many(symbols).unordered();
+/// while let Some((i, price)) = prices.next().await { ... }
+/// ```
+///
+/// Awaited, it's the result of each item, in order; one failure doesn't fail the others. Each
+/// request is an ordinary one, rate limited, retried & cached as the endpoint is; so many
+/// requests at once only wait for the API's `rate_limit:`, rather than exceed it.
+#[must_use = "requests are only sent once it's awaited, or streamed"]
+pub struct Many<'a, T> {
+    api: &'a Api,
+    endpoint: &'static str,
+    format: Format,
+    envelope: Option<Envelope>,
+    requests: Vec<Result<Call>>,
+    concurrency: usize,
+    _data: PhantomData<fn() -> T>,
+}
+
+impl Api {
+    /// Send each of `requests`, decoding their responses as `format`; `endpoint` names them in
+    /// any error.
+    pub fn many<T>(
+        &self,
+        endpoint: &'static str,
+        format: Format,
+        envelope: Option<Envelope>,
+        requests: Vec<Result<Call>>,
+    ) -> Many<'_, T> {
+        Many {
+            api: self,
+            endpoint,
+            format,
+            envelope,
+            requests,
+            concurrency: 8,
+            _data: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Many<'a, T>
+where
+    T: DeserializeOwned + Send + 'a,
+{
+    /// Send up to `concurrency` requests at once; 8, by default.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// The result of each item, in order, as soon as it and those before it are done.
+    pub fn stream(self) -> impl Stream<Item = Result<T>> + 'a {
+        let concurrency = self.concurrency;
+        self.requests()
+            .buffered(concurrency)
+            .map(|(_, result)| result)
+    }
+
+    /// The result of each item, by its index, as soon as it's done.
+    pub fn unordered(self) -> impl Stream<Item = (usize, Result<T>)> + 'a {
+        let concurrency = self.concurrency;
+        self.requests().buffer_unordered(concurrency)
+    }
+
+    // a future of each request, by its index
+    fn requests(self) -> impl Stream<Item = BoxFuture<'a, (usize, Result<T>)>> + 'a {
+        let Self {
+            api,
+            endpoint,
+            format,
+            envelope,
+            requests,
+            ..
+        } = self;
+        let requests = requests.into_iter().enumerate();
+        stream::iter(requests).map(move |(i, request)| {
+            let envelope = envelope.clone();
+            async move {
+                let result = match request {
+                    Ok(request) => {
+                        api.decode(endpoint, format, envelope.as_ref(), request)
+                            .await
+                    }
+                    Err(e) => Err(e),
+                };
+                (i, result)
+            }
+            .boxed()
+        })
+    }
+}
+
+impl<'a, T> IntoFuture for Many<'a, T>
+where
+    T: DeserializeOwned + Send + 'a,
+{
+    type Output = Vec<Result<T>>;
+    type IntoFuture = BoxFuture<'a, Vec<Result<T>>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.stream().collect().boxed()
+    }
+}
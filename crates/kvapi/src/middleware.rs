@@ -0,0 +1,60 @@
+use crate::Result;
+use async_trait::async_trait;
+use reqwest::{Request, Response};
+use std::fmt;
+
+/// A hook around every request of an API; to log, sign, or rewrite them.
+///
+/// ```rust,ignore
+/// struct CorrelationId;
+///
+/// #[kvapi::async_trait]
+/// impl kvapi::Middleware for CorrelationId {
+///     async fn before(&self, request: &mut kvapi::Request) -> kvapi::Result<()> {
+///         let id = uuid::Uuid::new_v4().to_string();
+///         request.headers_mut().insert("X-Correlation-Id", id.parse().unwrap());
+///         Ok(())
+///     }
+/// }
+///
+/// kvapi::api! {
+///     name:       Fred
+///     middleware: [CorrelationId, Logging::default()]
+///     dict:       { ... }
+/// }
+///
+/// let fred = Fred::new().with_middleware(Audit::new(log)); // or at runtime
+/// ```
+///
+/// `before` sees the request as it's about to be sent, with its method, URL, headers & body,
+/// once the API's headers & query are set; `after` sees the response before it's checked,
+/// or decoded. Middleware runs in the order it's given, and in reverse for `after`; except
+/// for a [`Signer`](crate::Signer), which runs after all the others, whatever its place.
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    async fn before(&self, request: &mut Request) -> Result<()> {
+        let _ = request;
+        Ok(())
+    }
+
+    async fn after(&self, response: Response) -> Result<Response> {
+        Ok(response)
+    }
+
+    /// Whether it signs requests, and so runs after every other middleware; true of any
+    /// [`Signer`](crate::Signer).
+    fn is_signer(&self) -> bool {
+        false
+    }
+
+    /// Name of the middleware, in `Debug` output.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl fmt::Debug for dyn Middleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
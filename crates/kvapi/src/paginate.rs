@@ -0,0 +1,348 @@
+use crate::{Api, Call, Envelope, Error, Format, Result};
+use futures::{stream, Stream, StreamExt};
+use reqwest::header::{HeaderMap, LINK};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use url::Url;
+
+/// How the pages of an endpoint follow one another, from `#[paginate(..)]` on a dict entry.
+///
+/// ```rust,ignore
+/// #[paginate(offset = "offset", limit = "limit", total = "count", items = "seriess")]
+/// "category/series": CategorySeries,
+///
+/// #[paginate(cursor = "cursor", next = "meta/next_cursor", items = "data")]
+/// "trades": Trades,
+///
+/// #[paginate(link, item = Trade)]
+/// "history": Vec<Trade>,
+/// ```
+///
+/// The endpoint's request builder, and the endpoint itself, then have `pages()`, a `Stream` of
+/// each page decoded as the entry's type, and `items()`, of the items within every page. A page
+/// is only requested once the one before it is read; a failed page ends the stream, and so does
+/// an offset page with the same items as the one before it, i.e., from a server that ignores the
+/// offset.
+///
+/// Fields are `/`-separated paths within the data of a JSON response (i.e., once any envelope is
+/// opened); without `items`, the data is the list of items itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Paginate {
+    by: By,
+    items: Option<&'static str>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum By {
+    // `?offset=`, advanced by the items of each page; until a page is empty, short of `size`,
+    // reaches the `total`, or repeats the last one
+    Offset {
+        offset: &'static str,
+        limit: Option<&'static str>,
+        size: Option<u64>,
+        total: Option<&'static str>,
+    },
+    // `?cursor=`, taken from the `next` field of each page; until it's missing, or empty
+    Cursor {
+        cursor: &'static str,
+        next: &'static str,
+    },
+    // the `rel="next"` URL of the `Link` header; until there's none
+    Link,
+}
+
+impl Paginate {
+    /// Pages by the `offset` query parameter; the index of a page's first item.
+    pub fn offset(offset: &'static str) -> Self {
+        Self {
+            by: By::Offset {
+                offset,
+                limit: None,
+                size: None,
+                total: None,
+            },
+            items: None,
+        }
+    }
+
+    /// Pages by the `cursor` query parameter, taken from the `next` field of each page.
+    pub fn cursor(cursor: &'static str, next: &'static str) -> Self {
+        Self {
+            by: By::Cursor { cursor, next },
+            items: None,
+        }
+    }
+
+    /// Pages by the `rel="next"` URL of each response's `Link` header.
+    pub fn link() -> Self {
+        Self {
+            by: By::Link,
+            items: None,
+        }
+    }
+
+    /// The query parameter of the page size, of offset pages; a page with fewer items is the last.
+    pub fn limit(mut self, limit: &'static str) -> Self {
+        if let By::Offset { limit: l, .. } = &mut self.by {
+            *l = Some(limit);
+        }
+        self
+    }
+
+    /// The page size, of offset pages; set as the `limit`, unless the request sets it.
+    pub fn size(mut self, size: u64) -> Self {
+        if let By::Offset { size: s, .. } = &mut self.by {
+            *s = Some(size);
+        }
+        self
+    }
+
+    /// The field of the total count of items, of offset pages.
+    pub fn total(mut self, total: &'static str) -> Self {
+        if let By::Offset { total: t, .. } = &mut self.by {
+            *t = Some(total);
+        }
+        self
+    }
+
+    /// The field of the list of items, within each page.
+    pub fn items(mut self, items: &'static str) -> Self {
+        self.items = Some(items);
+        self
+    }
+
+    // the URL of the first page, with the page size, if it's not set
+    fn first(&self, mut url: Url) -> Url {
+        if let By::Offset {
+            limit: Some(limit),
+            size: Some(size),
+            ..
+        } = self.by
+        {
+            if param(&url, limit).is_none() {
+                url.query_pairs_mut().append_pair(limit, &size.to_string());
+            }
+        }
+        url
+    }
+
+    // the URL of the page after `page`, requested at `url`, if there's one
+    fn next(&self, url: &Url, page: &Value, headers: &HeaderMap) -> Option<Url> {
+        match self.by {
+            By::Offset {
+                offset,
+                limit,
+                total,
+                ..
+            } => {
+                let count = self.list(page).map_or(0, Vec::len) as u64;
+                let from = param(url, offset)
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(0u64);
+                let size = limit.and_then(|limit| param(url, limit)?.parse().ok());
+                let total = total.and_then(|total| field(page, total)?.as_u64());
+                let next = from + count;
+                if count == 0 || size.is_some_and(|size| count < size) {
+                    return None;
+                }
+                if total.is_some_and(|total| next >= total) {
+                    return None;
+                }
+                Some(with_param(url, offset, &next.to_string()))
+            }
+            By::Cursor { cursor, next } => {
+                let next = match field(page, next)? {
+                    Value::String(next) => next.clone(),
+                    Value::Number(next) => next.to_string(),
+                    _ => return None,
+                };
+                if next.is_empty() || param(url, cursor).as_deref() == Some(next.as_str()) {
+                    return None;
+                }
+                Some(with_param(url, cursor, &next))
+            }
+            By::Link => {
+                let next = link(headers, "next")?;
+                let next = url.join(&next).ok()?;
+                (next != *url).then_some(next)
+            }
+        }
+    }
+
+    // the items of an offset page, to tell whether the next one repeats them
+    fn seen(&self, page: &Value) -> Option<Vec<Value>> {
+        match self.by {
+            By::Offset { .. } => self.list(page).cloned(),
+            By::Cursor { .. } | By::Link => None,
+        }
+    }
+
+    // the items of a page; the page itself, if it's a list
+    fn list<'v>(&self, page: &'v Value) -> Option<&'v Vec<Value>> {
+        let items = match self.items {
+            Some(items) => field(page, items)?,
+            None => page,
+        };
+        items.as_array()
+    }
+
+    // each item of a page, decoded as `T`
+    fn decode<T>(&self, endpoint: &'static str, page: Value) -> Vec<Result<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let name = self.items.unwrap_or_default().replace('/', ".");
+        let Some(items) = self.list(&page) else {
+            let error = format!("missing list of items `{name}`");
+            return vec![Err(Error::decode(endpoint, name, error, page.to_string()))];
+        };
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                serde_path_to_error::deserialize(item).map_err(|e| {
+                    let path = match e.path().to_string() {
+                        path if path == "." => format!("{name}[{i}]"),
+                        path if path.starts_with('[') => format!("{name}[{i}]{path}"),
+                        path => format!("{name}[{i}].{path}"),
+                    };
+                    Error::decode(endpoint, path, e.into_inner(), item.to_string())
+                })
+            })
+            .collect()
+    }
+}
+
+impl Api {
+    /// Send `request`, then the request of each page after it, decoding each page as `format`;
+    /// `endpoint` names it in any error.
+    pub fn pages<T>(
+        &self,
+        endpoint: &'static str,
+        format: Format,
+        envelope: Option<Envelope>,
+        paginate: Paginate,
+        request: Result<Call>,
+    ) -> impl Stream<Item = Result<T>> + '_
+    where
+        T: DeserializeOwned,
+    {
+        self.paginate(endpoint, format, envelope, paginate, request)
+            .map(move |page| {
+                let page = page?;
+                serde_path_to_error::deserialize(&page).map_err(|e| {
+                    let path = e.path().to_string();
+                    Error::decode(endpoint, path, e.into_inner(), page.to_string())
+                })
+            })
+    }
+
+    /// `pages()`, taking the items out of each page, in order.
+    pub fn items<'a, T>(
+        &'a self,
+        endpoint: &'static str,
+        format: Format,
+        envelope: Option<Envelope>,
+        paginate: Paginate,
+        request: Result<Call>,
+    ) -> impl Stream<Item = Result<T>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+    {
+        self.paginate(endpoint, format, envelope, paginate, request)
+            .flat_map(move |page| {
+                let items = match page {
+                    Ok(page) => paginate.decode(endpoint, page),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(items)
+            })
+    }
+
+    // each page, undecoded; the request is copied for each, to the page's URL
+    fn paginate(
+        &self,
+        endpoint: &'static str,
+        format: Format,
+        envelope: Option<Envelope>,
+        paginate: Paginate,
+        request: Result<Call>,
+    ) -> impl Stream<Item = Result<Value>> + '_ {
+        let first = request.and_then(|request| {
+            let url = paginate.first(request.url()?);
+            Ok((request, url, None))
+        });
+        stream::unfold(Some(first), move |state| {
+            let envelope = envelope.clone();
+            async move {
+                let (request, url, seen) = match state? {
+                    Ok(state) => state,
+                    Err(e) => return Some((Err(e), None)),
+                };
+                let call = match request.to(url.clone()) {
+                    Ok(call) => call,
+                    Err(e) => return Some((Err(e), None)),
+                };
+                let page =
+                    self.decode_with_meta::<Value>(endpoint, format, envelope.as_ref(), call);
+                match page.await {
+                    // the same items again; the offset is ignored, and there's no end to them
+                    Ok(page) if seen.is_some() && seen.as_ref() == paginate.list(&page.data) => {
+                        None
+                    }
+                    Ok(page) => {
+                        let next = paginate.next(&url, &page.data, &page.headers);
+                        let seen = paginate.seen(&page.data);
+                        let next = next.map(|url| Ok((request, url, seen)));
+                        Some((Ok(page.data), next))
+                    }
+                    Err(e) => Some((Err(e), None)),
+                }
+            }
+        })
+    }
+}
+
+// the value of a `/`-separated field
+fn field<'v>(value: &'v Value, path: &str) -> Option<&'v Value> {
+    value.pointer(&format!("/{path}"))
+}
+
+fn param(url: &Url, key: &str) -> Option<String> {
+    let mut pairs = url.query_pairs();
+    pairs
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.into_owned())
+}
+
+// the URL, with `key` replaced, or added
+fn with_param(url: &Url, key: &str, value: &str) -> Url {
+    let mut url = url.clone();
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(k, _)| k != key)
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(key, value);
+    url
+}
+
+// the target of a `Link` header, by its `rel`, i.e., `<https://api.github.com/..?page=2>; rel="next"`
+fn link(headers: &HeaderMap, rel: &str) -> Option<String> {
+    let links = headers.get_all(LINK).into_iter();
+    let links = links.filter_map(|value| value.to_str().ok());
+    links.flat_map(|value| value.split(',')).find_map(|link| {
+        let (target, params) = link.split_once(';')?;
+        let target = target.trim().strip_prefix('<')?.strip_suffix('>')?;
+        let mut params = params.split(';').map(str::trim);
+        params
+            .any(|param| {
+                let value = param.strip_prefix("rel=").unwrap_or_default();
+                value.trim_matches('"').split_whitespace().any(|r| r == rel)
+            })
+            .then(|| target.to_string())
+    })
+}
@@ -0,0 +1,187 @@
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+/// How a collection is written into a query string.
+///
+/// ```text
+/// Comma   ->  symbols=BTCUSDT,ETHUSDT
+/// Repeat  ->  symbols=BTCUSDT&symbols=ETHUSDT
+/// Json    ->  symbols=["BTCUSDT","ETHUSDT"]
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Style {
+    Comma,
+    #[default]
+    Repeat,
+    Json,
+}
+
+/// Query parameters of a request, kept in the order they were set.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    pairs: Vec<(String, String)>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a raw `key=value` pair; encoding happens when the URL is built.
+    pub fn push(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.pairs.push((key.into(), value.into()));
+    }
+
+    /// Set `key` to any `Serialize` value, replacing any previous value.
+    ///
+    /// `None` leaves the parameter out, and sequences are written according to `style`.
+    pub fn set<T>(&mut self, key: &str, value: &T, style: Style) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.pairs.retain(|(k, _)| k != key);
+
+        let error = |e| Error::Url(format!("query parameter `{key}`: {e}"));
+        let value = serde_json::to_value(value).map_err(|e| error(e.to_string()))?;
+        match (value, style) {
+            (Value::Null, _) => {}
+            (Value::Array(values), Style::Json) => {
+                let json = serde_json::to_string(&values).map_err(|e| error(e.to_string()))?;
+                self.push(key, json);
+            }
+            (Value::Array(values), Style::Comma) => {
+                let values = values
+                    .iter()
+                    .map(scalar)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+                self.push(key, values.join(","));
+            }
+            (Value::Array(values), Style::Repeat) => {
+                for value in values.iter() {
+                    let value = scalar(value).map_err(error)?;
+                    self.push(key, value);
+                }
+            }
+            (value, _) => {
+                let value = scalar(&value)
+                    .map_err(|_| error("expected a scalar or a sequence".to_string()))?;
+                self.push(key, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add every pair of `other`, replacing any keys that are already set.
+    pub fn merge(&mut self, other: Query) {
+        self.pairs
+            .retain(|(k, _)| !other.pairs.iter().any(|(key, _)| key == k));
+        self.pairs.extend(other.pairs);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// `application/x-www-form-urlencoded` string, i.e., `a=1&b=two%20words`.
+    pub fn encode(&self) -> String {
+        let mut serializer = form_urlencoded::Serializer::new(String::new());
+        serializer.extend_pairs(self.iter());
+        serializer.finish()
+    }
+}
+
+impl From<&str> for Query {
+    /// Parse a raw query string, e.g., `"?category_id=125&"`, ignoring any `?` and `&` around it.
+    fn from(raw: &str) -> Self {
+        let raw = raw.trim_start_matches('?');
+        let pairs = form_urlencoded::parse(raw.as_bytes())
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+        Self { pairs }
+    }
+}
+
+impl From<String> for Query {
+    fn from(raw: String) -> Self {
+        Self::from(raw.as_str())
+    }
+}
+
+impl From<&String> for Query {
+    fn from(raw: &String) -> Self {
+        Self::from(raw.as_str())
+    }
+}
+
+impl From<&Query> for Query {
+    fn from(query: &Query) -> Self {
+        query.clone()
+    }
+}
+
+// stringify a single query value; strings are not quoted
+fn scalar(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(format!(
+            "expected a string, number or bool, found `{value}`"
+        )),
+    }
+}
+
+/// Query parameters declared with `#[params(...)]`, as set through a generated request builder.
+///
+/// Setting a parameter never fails; any error is kept until the request is sent.
+#[derive(Clone, Debug, Default)]
+pub struct Params {
+    query: Query,
+    missing: Vec<&'static str>,
+    error: Option<String>,
+}
+
+impl Params {
+    /// Start with the required parameters, which must all be set before sending.
+    pub fn new(required: &[&'static str]) -> Self {
+        Self {
+            missing: required.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    pub fn set<T>(&mut self, key: &'static str, value: &T, style: Style)
+    where
+        T: Serialize + ?Sized,
+    {
+        self.missing.retain(|k| *k != key);
+        if let Err(e) = self.query.set(key, value, style) {
+            self.error.get_or_insert(e.to_string());
+        }
+    }
+
+    /// The query, as long as every required parameter is set, and all could be encoded.
+    pub fn query(&self) -> Result<&Query> {
+        if let Some(e) = &self.error {
+            return Err(Error::Url(e.clone()));
+        }
+        if !self.missing.is_empty() {
+            return Err(Error::Url(format!(
+                "missing required query parameter(s): `{}`",
+                self.missing.join("`, `")
+            )));
+        }
+        Ok(&self.query)
+    }
+
+    /// The query as set so far, without checking for missing parameters.
+    pub fn partial(&self) -> &Query {
+        &self.query
+    }
+}
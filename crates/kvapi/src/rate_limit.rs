@@ -0,0 +1,103 @@
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time::Instant};
+
+/// An async token bucket, from `rate_limit:` in `api!`; every endpoint of the API takes its
+/// `#[weight]` from it (1, by default) before each request.
+///
+/// ```rust,ignore
+/// kvapi::api! {
+///     name:       Binance
+///     rate_limit: 1200/min
+///     dict:       {
+///         #[weight: 20]
+///         "api/v3/depth": OrderBook,
+///         "api/v3/ticker/price": Ticker,
+///     }
+/// }
+///
+/// // two APIs of the same host, sharing the one limit
+/// let spot = Binance::new();
+/// let margin = BinanceMargin::new().with_rate_limit(spot.rate_limit().unwrap().clone());
+/// ```
+///
+/// The bucket starts full, and refills steadily, i.e., `1200/min` allows a burst of 1200, then
+/// one request every 50ms. Clones share the bucket; waiting requests are served in order.
+#[derive(Clone, Debug)]
+pub struct RateLimit {
+    capacity: u32,
+    per: Duration,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimit {
+    /// `capacity` requests (of weight 1) `per` period.
+    pub fn new(capacity: u32, per: Duration) -> Self {
+        assert!(
+            capacity > 0 && !per.is_zero(),
+            "a rate limit allows at least one request per period"
+        );
+        Self {
+            capacity,
+            per,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: capacity as f64,
+                updated: Instant::now(),
+            })),
+        }
+    }
+
+    pub fn per_second(capacity: u32) -> Self {
+        Self::new(capacity, Duration::from_secs(1))
+    }
+
+    pub fn per_minute(capacity: u32) -> Self {
+        Self::new(capacity, Duration::from_secs(60))
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn per(&self) -> Duration {
+        self.per
+    }
+
+    /// The whole tokens in the bucket, now.
+    pub async fn available(&self) -> u32 {
+        let mut bucket = self.bucket.lock().await;
+        self.refill(&mut bucket);
+        bucket.tokens as u32
+    }
+
+    /// Wait until `weight` tokens are available, and take them; a weight over the capacity
+    /// takes the whole bucket.
+    pub async fn acquire(&self, weight: u32) {
+        let weight = weight.min(self.capacity) as f64;
+        let mut bucket = self.bucket.lock().await; // held while waiting, so requests keep their turn
+        self.refill(&mut bucket);
+        if bucket.tokens < weight {
+            let wait = (weight - bucket.tokens) / self.rate();
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+            self.refill(&mut bucket);
+        }
+        bucket.tokens -= weight;
+    }
+
+    // tokens per second
+    fn rate(&self) -> f64 {
+        self.capacity as f64 / self.per.as_secs_f64()
+    }
+
+    fn refill(&self, bucket: &mut Bucket) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate()).min(self.capacity as f64);
+        bucket.updated = now;
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{cache::CacheStatus, url::Url};
+use reqwest::{header::HeaderMap, StatusCode};
+use std::{
+    ops::{Deref, DerefMut},
+    time::{Duration, SystemTime},
+};
+
+/// A decoded response, with what `get()` leaves out: its status, headers & timing.
+///
+/// ```rust,ignore
+/// let prices = binance.ticker.price.get_with_meta().await?;
+/// println!("{:?}", prices.header("x-mbx-used-weight-1m"));    // Some("2")
+/// for price in prices.iter() { ... }                          // `Deref` to the data
+/// ```
+#[derive(Clone, Debug)]
+pub struct Response<T> {
+    pub data: T,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub url: Url,          // after any redirects
+    pub elapsed: Duration, // from sending the request, to reading the whole body
+    pub sent_at: SystemTime,
+    pub cache: CacheStatus, // a `Miss`, unless it's served from the cache
+}
+
+impl<T> Response<T> {
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+
+    /// Value of the header `name`, if it's set, and text.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
+
+    /// The same response, with its data mapped by `f`.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Response<U> {
+        Response {
+            data: f(self.data),
+            status: self.status,
+            headers: self.headers,
+            url: self.url,
+            elapsed: self.elapsed,
+            sent_at: self.sent_at,
+            cache: self.cache,
+        }
+    }
+}
+
+impl<T> Deref for Response<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+impl<T> DerefMut for Response<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+}
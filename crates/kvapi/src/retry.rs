@@ -0,0 +1,179 @@
+use crate::{Error, Result};
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    Method,
+};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+/// When, and how often, a failed request is sent again, from `retry:` in `api!`, or
+/// `#[retry(..)]` on a dict entry.
+///
+/// ```rust,ignore
+/// retry: { attempts: 5, backoff: exponential(200ms, 10s), on: [429, 500..=599, timeout, connect] }
+///
+/// #[retry(attempts: 3, non_idempotent: true)]
+/// "orders": CreateOrder => OrderAck,
+/// ```
+///
+/// `attempts` counts the first request. Between attempts, a `Retry-After` header is honoured, up
+/// to `max_wait` (a minute, by default); asked to wait longer, the request isn't retried, and its
+/// error is returned as is. Otherwise, the backoff is jittered, to between half & all of its delay. Only idempotent
+/// methods (i.e., not `POST`, or `PATCH`) are retried, unless `non_idempotent` opts in.
+///
+/// Without `on`, the same failures as `Error::is_retryable()` are retried: a timeout, a failed
+/// connection, or a `408`, `429`, `500`, `502`, `503`, or `504`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Retry {
+    attempts: u32,
+    backoff: Backoff,
+    on: Vec<On>,
+    non_idempotent: bool,
+    max_wait: Duration, // of a `Retry-After`
+}
+
+/// Delay before the `n`th retry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backoff {
+    /// The same delay each time.
+    Constant(Duration),
+    /// `base`, doubled with each retry, up to `max`.
+    Exponential { base: Duration, max: Duration },
+}
+
+/// A failure to retry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum On {
+    /// A status within `from..=to`, i.e., `On::Status(500, 599)`.
+    Status(u16, u16),
+    /// The request, or its connection, timed out.
+    Timeout,
+    /// The connection couldn't be made.
+    Connect,
+}
+
+impl Retry {
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            backoff: Backoff::exponential(Duration::from_millis(200), Duration::from_secs(10)),
+            on: vec![
+                On::Status(408, 408),
+                On::Status(429, 429),
+                On::Status(500, 500),
+                On::Status(502, 504),
+                On::Timeout,
+                On::Connect,
+            ],
+            non_idempotent: false,
+            max_wait: Duration::from_secs(60),
+        }
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Retry only these failures.
+    pub fn on(mut self, on: impl IntoIterator<Item = On>) -> Self {
+        self.on = on.into_iter().collect();
+        self
+    }
+
+    /// Retry `POST` & `PATCH` requests too; only if the server can tell a repeat.
+    pub fn non_idempotent(mut self, non_idempotent: bool) -> Self {
+        self.non_idempotent = non_idempotent;
+        self
+    }
+
+    /// The longest `Retry-After` to wait for; a longer one isn't retried.
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub(crate) fn allows(&self, method: &Method) -> bool {
+        self.non_idempotent || !matches!(*method, Method::POST | Method::PATCH)
+    }
+
+    // the delay before sending the request again, after `attempt`s; none if it's done
+    pub(crate) fn delay(
+        &self,
+        attempt: u32,
+        result: &Result<reqwest::Response>,
+    ) -> Option<Duration> {
+        if attempt >= self.attempts {
+            return None;
+        }
+        let retry = match result {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                self.on.iter().any(|on| match *on {
+                    On::Status(from, to) => (from..=to).contains(&status),
+                    _ => false,
+                })
+            }
+            Err(Error::Timeout(_)) => self.on.contains(&On::Timeout),
+            Err(Error::Transport(e)) => e.is_connect() && self.on.contains(&On::Connect),
+            Err(_) => false,
+        };
+        if !retry {
+            return None;
+        }
+
+        let headers = result.as_ref().ok().map(|response| response.headers());
+        match headers.and_then(retry_after) {
+            Some(wait) if wait > self.max_wait => None,
+            Some(wait) => Some(wait),
+            None => Some(self.backoff.delay(attempt)),
+        }
+    }
+}
+
+impl Backoff {
+    pub fn constant(delay: Duration) -> Self {
+        Self::Constant(delay)
+    }
+
+    pub fn exponential(base: Duration, max: Duration) -> Self {
+        Self::Exponential { base, max }
+    }
+
+    /// The delay after `attempt` (from 1), jittered.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay = match *self {
+            Self::Constant(delay) => delay,
+            Self::Exponential { base, max } => {
+                let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+                base.saturating_mul(factor).min(max)
+            }
+        };
+        jitter(delay)
+    }
+}
+
+// between half & all of `delay`
+fn jitter(delay: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    let fraction = (random >> 11) as f64 / (1u64 << 53) as f64; // [0, 1)
+    delay / 2 + delay.mul_f64(fraction / 2.0)
+}
+
+/// How long a response asks to wait, by its `Retry-After` header, in seconds or as a date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.signed_duration_since(chrono::Utc::now());
+    Some(wait.to_std().unwrap_or_default())
+}
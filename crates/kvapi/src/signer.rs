@@ -0,0 +1,276 @@
+use crate::{Error, Middleware, Result};
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use hmac::{Hmac, Mac};
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Request,
+};
+use sha2::{Digest, Sha256, Sha512};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Signs each request of an API; any `Signer` is a `Middleware`.
+///
+/// ```rust,ignore
+/// kvapi::api! {
+///     name:       KuCoin
+///     middleware: [kvapi::signer::KuCoin::new(key, secret, passphrase)]
+///     dict:       { ... }
+/// }
+/// ```
+///
+/// The signer sees the request as it's sent, once every other middleware has run, wherever it's
+/// listed.
+/// Built-in: [`Binance`], [`KuCoin`] & [`Kraken`].
+pub trait Signer: Send + Sync + 'static {
+    fn sign(&self, payload: &Payload<'_>) -> Result<Signed>;
+}
+
+/// What a request is signed with, as it's sent.
+#[derive(Clone, Copy, Debug)]
+pub struct Payload<'a> {
+    pub method: &'a str, // i.e., `"GET"`
+    pub path: &'a str,   // i.e., `"/api/v3/order"`
+    pub query: &'a str,  // without the `?`; empty if none
+    pub body: &'a [u8],  // empty if none
+    pub timestamp: u64,  // milliseconds since the epoch; one per request
+}
+
+/// What a signer adds to a request: headers, query parameters, or a new body.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Signed {
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, String)>, // appended, in order
+    pub body: Option<Vec<u8>>,        // replaces the body
+}
+
+impl Signed {
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+}
+
+#[async_trait]
+impl<S: Signer> Middleware for S {
+    async fn before(&self, request: &mut Request) -> Result<()> {
+        let body = match request.body() {
+            None => &[][..],
+            Some(body) => body
+                .as_bytes()
+                .ok_or_else(|| Error::middleware("a streamed body can't be signed"))?,
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(Error::middleware)?
+            .as_millis() as u64;
+        let payload = Payload {
+            method: request.method().as_str(),
+            path: request.url().path(),
+            query: request.url().query().unwrap_or_default(),
+            body,
+            timestamp,
+        };
+        let signed = self.sign(&payload)?;
+
+        for (name, value) in signed.headers {
+            let name = HeaderName::try_from(name).map_err(Error::middleware)?;
+            let value = HeaderValue::try_from(value).map_err(Error::middleware)?;
+            request.headers_mut().insert(name, value);
+        }
+        if !signed.query.is_empty() {
+            request
+                .url_mut()
+                .query_pairs_mut()
+                .extend_pairs(signed.query);
+        }
+        if let Some(body) = signed.body {
+            *request.body_mut() = Some(body.into());
+        }
+        Ok(())
+    }
+
+    fn is_signer(&self) -> bool {
+        true
+    }
+}
+
+/// HMAC-SHA256 of `message`.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// HMAC-SHA512 of `message`.
+pub fn hmac_sha512(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Binance's `SIGNED` endpoints: a `timestamp` & a hex HMAC-SHA256 `signature` of the query
+/// & body, as query parameters, with the `X-MBX-APIKEY` header.
+///
+/// Docs: <https://developers.binance.com/docs/binance-spot-api-docs/rest-api/request-security>
+#[derive(Clone)]
+pub struct Binance {
+    key: String,
+    secret: String,
+}
+
+impl Binance {
+    pub fn new(key: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            secret: secret.into(),
+        }
+    }
+}
+
+impl Signer for Binance {
+    fn sign(&self, payload: &Payload<'_>) -> Result<Signed> {
+        let timestamp = payload.timestamp.to_string();
+        let mut message = match payload.query {
+            "" => format!("timestamp={timestamp}").into_bytes(),
+            query => format!("{query}&timestamp={timestamp}").into_bytes(),
+        };
+        message.extend_from_slice(payload.body);
+        let signature = hex(&hmac_sha256(self.secret.as_bytes(), &message));
+
+        Ok(Signed::default()
+            .header("X-MBX-APIKEY", &self.key)
+            .query("timestamp", timestamp)
+            .query("signature", signature))
+    }
+}
+
+/// KuCoin's v2 API keys: a base64 HMAC-SHA256 of the timestamp, method, path, query & body,
+/// and of the passphrase, as `KC-API-*` headers.
+///
+/// Docs: <https://www.kucoin.com/docs/basic-info/connection-method/authentication/signing-a-message>
+#[derive(Clone)]
+pub struct KuCoin {
+    key: String,
+    secret: String,
+    passphrase: String,
+}
+
+impl KuCoin {
+    pub fn new(
+        key: impl Into<String>,
+        secret: impl Into<String>,
+        passphrase: impl Into<String>,
+    ) -> Self {
+        Self {
+            key: key.into(),
+            secret: secret.into(),
+            passphrase: passphrase.into(),
+        }
+    }
+}
+
+impl Signer for KuCoin {
+    fn sign(&self, payload: &Payload<'_>) -> Result<Signed> {
+        let timestamp = payload.timestamp.to_string();
+        let endpoint = match payload.query {
+            "" => payload.path.to_string(),
+            query => format!("{}?{query}", payload.path),
+        };
+        let mut message = format!("{timestamp}{}{endpoint}", payload.method).into_bytes();
+        message.extend_from_slice(payload.body);
+
+        let secret = self.secret.as_bytes();
+        let signature = BASE64_STANDARD.encode(hmac_sha256(secret, &message));
+        let passphrase = BASE64_STANDARD.encode(hmac_sha256(secret, self.passphrase.as_bytes()));
+
+        Ok(Signed::default()
+            .header("KC-API-KEY", &self.key)
+            .header("KC-API-SIGN", signature)
+            .header("KC-API-TIMESTAMP", timestamp)
+            .header("KC-API-PASSPHRASE", passphrase)
+            .header("KC-API-KEY-VERSION", "2"))
+    }
+}
+
+/// Kraken's private endpoints (`/0/private/..`): a `nonce` prepended to the form body, and a
+/// base64 HMAC-SHA512 of the path & the SHA256 of the nonce & body, as the `API-Sign` header.
+///
+/// The secret is the base64 one, as given by Kraken; public endpoints are left unsigned. The
+/// nonce is the timestamp, unless that's not above the last one, i.e., two requests within a
+/// millisecond; then it's the last one's, plus one. Clones share the last nonce.
+///
+/// Docs: <https://docs.kraken.com/api/docs/guides/spot-rest-auth>
+#[derive(Clone)]
+pub struct Kraken {
+    key: String,
+    secret: String,
+    nonce: Arc<AtomicU64>, // the last one sent
+}
+
+impl Kraken {
+    pub fn new(key: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            secret: secret.into(),
+            nonce: Arc::default(),
+        }
+    }
+
+    // `timestamp`, or the last nonce plus one, if that's more
+    fn nonce(&self, timestamp: u64) -> u64 {
+        let next = |last: u64| timestamp.max(last + 1);
+        let update = |last| Some(next(last));
+        // never an `Err`, as there's always a next one
+        let (relaxed, nonce) = (Ordering::Relaxed, &self.nonce);
+        let last = nonce.fetch_update(relaxed, relaxed, update);
+        next(last.unwrap_or_else(|last| last))
+    }
+}
+
+impl Signer for Kraken {
+    fn sign(&self, payload: &Payload<'_>) -> Result<Signed> {
+        if !payload.path.contains("/private/") {
+            return Ok(Signed::default());
+        }
+
+        let nonce = self.nonce(payload.timestamp).to_string();
+        let body = match payload.body {
+            [] => format!("nonce={nonce}"),
+            body => format!("nonce={nonce}&{}", String::from_utf8_lossy(body)),
+        };
+        let mut message = payload.path.as_bytes().to_vec();
+        message.extend(Sha256::digest(format!("{nonce}{body}")));
+
+        let secret = BASE64_STANDARD
+            .decode(&self.secret)
+            .map_err(Error::middleware)?;
+        let signature = BASE64_STANDARD.encode(hmac_sha512(&secret, &message));
+
+        Ok(Signed::default()
+            .header("API-Key", &self.key)
+            .header("API-Sign", signature)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body))
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{query::Query, Error};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+pub use reqwest::Url;
+
+/// Characters that are percent-encoded in a single path segment.
+///
+/// This is the WHATWG path set, plus `/` and `%`, so that a value can never
+/// escape its segment, e.g., `"a/b"` -> `"a%2Fb"`; nor can `.` or `..`, as
+/// `UrlBuilder::build()` rejects them.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}')
+    .add(b'/')
+    .add(b'%');
+
+/// Percent-encode a path parameter, i.e., the `{series_id}` in `"series/{series_id}/observations"`.
+///
+/// ```rust
+/// assert_eq!(kvapi::url::encode_path("BTC/USDT"), "BTC%2FUSDT");
+/// assert_eq!(kvapi::url::encode_path(42), "42");
+/// assert_eq!(kvapi::url::encode_path("%2e%2e"), "%252e%252e");
+/// ```
+pub fn encode_path(value: impl std::fmt::Display) -> String {
+    utf8_percent_encode(&value.to_string(), SEGMENT).to_string()
+}
+
+/// Percent-encode a query value of the key, i.e., the `{symbol}` in `"ticker/price?symbol={symbol}"`;
+/// as a form value, so it can't end its parameter, or add another.
+///
+/// ```rust
+/// assert_eq!(kvapi::url::encode_query("BTC&symbol=ETH"), "BTC%26symbol%3DETH");
+/// assert_eq!(kvapi::url::encode_query("a+b c"), "a%2Bb+c");
+/// ```
+pub fn encode_query(value: impl std::fmt::Display) -> String {
+    form_urlencoded::byte_serialize(value.to_string().as_bytes()).collect()
+}
+
+/// Builds the final URL of a request, from the `base`, the endpoint, and each level of query.
+///
+/// ```rust
+/// use kvapi::url::UrlBuilder;
+///
+/// let url = UrlBuilder::new()
+///     .base("https://api.stlouisfed.org/fred/")   // `base:`
+///     .query("api_key=abc&file_type=json")        // global `query:`
+///     .path("/series/observations?series_id=GDP") // the dict key
+///     .query("?limit=10&")                        // `#[query]`
+///     .query("series_id=DGS10")                   // per-call params
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(
+///     url.as_str(),
+///     "https://api.stlouisfed.org/fred/series/observations?api_key=abc&file_type=json&limit=10&series_id=DGS10"
+/// );
+/// ```
+///
+/// - segments are joined with exactly one `/`, whether or not either side has one
+/// - each call to `query()` is a level; a key set in a later level replaces any earlier value,
+///   and is moved to the end
+/// - any `?` and `&` around a raw query are ignored, and values are percent-encoded
+/// - the result must be a valid, absolute `http`/`https` URL, without a `.` or `..` segment
+#[derive(Clone, Debug, Default)]
+pub struct UrlBuilder {
+    segments: Vec<String>,
+    query: Query,
+}
+
+impl UrlBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The root of every endpoint, e.g., `https://api.binance.com/api/v3/`.
+    pub fn base(self, base: &str) -> Self {
+        self.path(base)
+    }
+
+    /// Join a path to the URL; anything after a `?` is added as a level of query.
+    pub fn path(mut self, path: &str) -> Self {
+        let (path, query) = match path.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (path, None),
+        };
+        if !path.is_empty() {
+            self.segments.push(path.to_string());
+        }
+        match query {
+            Some(query) => self.query(query),
+            None => self,
+        }
+    }
+
+    /// Add a level of query, overriding any key that was already set.
+    pub fn query(mut self, query: impl Into<Query>) -> Self {
+        self.query.merge(query.into());
+        self
+    }
+
+    pub fn build(&self) -> crate::Result<Url> {
+        let mut url = String::new();
+        for segment in self.segments.iter() {
+            if url.is_empty() {
+                url.push_str(segment);
+            } else {
+                url.truncate(url.trim_end_matches('/').len());
+                url.push('/');
+                url.push_str(segment.trim_start_matches('/'));
+            }
+        }
+        // a `.` or `..` would be dropped, or go up a segment, once parsed; i.e., a path parameter
+        let mut segments = self.segments.iter().flat_map(|segment| segment.split('/'));
+        if segments.any(is_dot) {
+            return Err(Error::Url(format!(
+                "malformed URL `{url}`: a segment can't be `.` or `..`"
+            )));
+        }
+        if !self.query.is_empty() {
+            url.push('?');
+            url.push_str(&self.query.encode());
+        }
+
+        let parsed = Url::parse(&url).map_err(|e| match e {
+            ::url::ParseError::RelativeUrlWithoutBase => Error::Url(format!(
+                "malformed URL `{url}`: expected an absolute URL; is `base` missing?"
+            )),
+            e => Error::Url(format!("malformed URL `{url}`: {e}")),
+        })?;
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.host().is_none() {
+            return Err(Error::Url(format!(
+                "malformed URL `{url}`: expected an `http` or `https` URL with a host"
+            )));
+        }
+        Ok(parsed)
+    }
+}
+
+// `.` or `..`, as the URL parser reads them
+fn is_dot(segment: &str) -> bool {
+    let segment = segment.to_ascii_lowercase();
+    matches!(
+        segment.as_str(),
+        "." | ".." | "%2e" | ".%2e" | "%2e." | "%2e%2e"
+    )
+}
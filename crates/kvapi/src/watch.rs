@@ -0,0 +1,264 @@
+use crate::{retry::Backoff, Api, Call, Envelope, Format, Result};
+use futures::{
+    future::{self, Either},
+    stream::{self, BoxStream},
+    Stream, StreamExt,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    collections::hash_map::DefaultHasher,
+    future::Future,
+    hash::{Hash, Hasher},
+    pin::{pin, Pin},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio_util::sync::CancellationToken;
+
+/// The responses of an endpoint, polled every `interval`, from its `watch()`; a `Stream` of each
+/// decoded as the entry's type.
+///
+/// ```rust,ignore
+/// let token = kvapi::CancellationToken::new();
+/// let mut ticker = binance.ticker.request().symbol("BTCUSDT").watch(Duration::from_secs(1))
+///     .distinct()                                         // only when the body changes
+///     .skip_errors(Backoff::exponential(ms(500), secs(30))) // rather than yield them
+///     .until(token.clone());                              // ends once it's cancelled
+///
+/// while let Some(ticker) = ticker.next().await { ... }
+/// ```
+///
+/// The first request is sent when the stream is first polled, and each next one `interval`
+/// after the response before it. By default, every response is yielded, and so is every error,
+/// without ending the stream; only a request that can't be sent again (with a streamed body)
+/// ends it, after its error.
+///
+/// Each poll is an ordinary request, rate limited, retried & cached as the endpoint is; with a
+/// `#[cache]` ttl shorter than the interval, each poll is a conditional request, and an
+/// unchanged body is a `304 Not Modified`.
+pub struct Watch<'a, T> {
+    api: &'a Api,
+    endpoint: &'static str,
+    format: Format,
+    envelope: Option<Envelope>,
+    request: Option<Result<Call>>,
+    interval: Duration,
+    distinct: Distinct<T>,
+    backoff: Option<Backoff>,
+    cancel: Option<CancellationToken>,
+    stream: Option<BoxStream<'a, Result<T>>>,
+}
+
+// which responses are yielded
+enum Distinct<T> {
+    All,
+    // a body that hashes differently than the last
+    Body,
+    // a value unequal to the last; `(eq, clone)`
+    Eq(fn(&T, &T) -> bool, fn(&T) -> T),
+}
+
+// what's left of `Watch`, once it's polled
+struct State<'a, T> {
+    api: &'a Api,
+    endpoint: &'static str,
+    format: Format,
+    envelope: Option<Envelope>,
+    request: Option<Call>, // none once it can't be copied
+    interval: Duration,
+    distinct: Distinct<T>,
+    backoff: Option<Backoff>,
+    cancel: Option<CancellationToken>,
+    wait: Option<Duration>, // before the next request; none for the first
+    failures: u32,          // in a row
+    hash: Option<u64>,
+    last: Option<T>,
+}
+
+impl Api {
+    /// Poll `request` every `interval`, decoding each response as `format`; `endpoint` names it
+    /// in any error.
+    pub fn watch<T>(
+        &self,
+        endpoint: &'static str,
+        format: Format,
+        envelope: Option<Envelope>,
+        request: Result<Call>,
+        interval: Duration,
+    ) -> Watch<'_, T> {
+        Watch {
+            api: self,
+            endpoint,
+            format,
+            envelope,
+            request: Some(request),
+            interval,
+            distinct: Distinct::All,
+            backoff: None,
+            cancel: None,
+            stream: None,
+        }
+    }
+}
+
+impl<'a, T> Watch<'a, T> {
+    /// Yield a response only if its body differs from the last one's, by its hash.
+    pub fn distinct(mut self) -> Self {
+        self.distinct = Distinct::Body;
+        self
+    }
+
+    /// Yield a response only if it's unequal to the last one, once decoded.
+    pub fn distinct_eq(mut self) -> Self
+    where
+        T: PartialEq + Clone,
+    {
+        self.distinct = Distinct::Eq(T::eq, T::clone);
+        self
+    }
+
+    /// Skip any failed request, rather than yield its error; the next one is sent after the
+    /// `Retry-After` of the response, if any, or else after `backoff`, by the failures in a row.
+    pub fn skip_errors(mut self, backoff: Backoff) -> Self {
+        self.backoff = Some(backoff);
+        self
+    }
+
+    /// End the stream once `token` is cancelled, even while a request is in flight.
+    pub fn until(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+}
+
+impl<'a, T> Watch<'a, T>
+where
+    T: DeserializeOwned + Send + 'a,
+{
+    fn start(&mut self) -> BoxStream<'a, Result<T>> {
+        let request = match self.request.take() {
+            Some(Ok(request)) => request,
+            Some(Err(e)) => return stream::iter([Err(e)]).boxed(),
+            None => return stream::empty().boxed(),
+        };
+        let state = State {
+            api: self.api,
+            endpoint: self.endpoint,
+            format: self.format,
+            envelope: self.envelope.take(),
+            request: Some(request),
+            interval: self.interval,
+            distinct: std::mem::replace(&mut self.distinct, Distinct::All),
+            backoff: self.backoff,
+            cancel: self.cancel.take(),
+            wait: None,
+            failures: 0,
+            hash: None,
+            last: None,
+        };
+        stream::unfold(state, State::next).boxed()
+    }
+}
+
+impl<'a, T> Stream for Watch<'a, T>
+where
+    T: DeserializeOwned + Send + 'a,
+{
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.stream.is_none() {
+            self.stream = Some(self.start());
+        }
+        self.stream.as_mut().unwrap().poll_next_unpin(cx)
+    }
+}
+
+impl<'a, T> State<'a, T>
+where
+    T: DeserializeOwned + Send + 'a,
+{
+    // the next value (or error) to yield; none once cancelled, or after the request couldn't be
+    // copied
+    async fn next(mut self) -> Option<(Result<T>, Self)> {
+        let cancel = self.cancel.clone();
+        loop {
+            if let Some(wait) = self.wait.take() {
+                cancellable(cancel.as_ref(), tokio::time::sleep(wait)).await?;
+            }
+            let request = match self.request.as_ref()?.again() {
+                Ok(request) => request,
+                // i.e., a streamed body; it's yielded, even if errors are skipped, then the end
+                Err(e) => {
+                    self.request = None;
+                    return Some((Err(e), self));
+                }
+            };
+            let response = cancellable(cancel.as_ref(), self.api.text_with_meta(request)).await?;
+
+            let value = response.and_then(|response| {
+                let hash = matches!(self.distinct, Distinct::Body).then(|| hash(&response.data));
+                if hash.is_some() && hash == self.hash {
+                    return Ok(None);
+                }
+                let envelope = self.envelope.as_ref();
+                let value = self
+                    .api
+                    .open::<T>(self.endpoint, self.format, envelope, response)?;
+                self.hash = hash;
+                Ok(Some(value.into_inner()))
+            });
+            let value = match value {
+                Ok(value) => {
+                    self.failures = 0;
+                    value.filter(|value| self.is_new(value)).map(Ok)
+                }
+                Err(e) => match self.backoff {
+                    Some(backoff) => {
+                        self.failures += 1;
+                        let wait = e.retry_after();
+                        self.wait = Some(wait.unwrap_or_else(|| backoff.delay(self.failures)));
+                        continue;
+                    }
+                    None => Some(Err(e)),
+                },
+            };
+            self.wait = Some(self.interval);
+            if let Some(value) = value {
+                return Some((value, self));
+            }
+        }
+    }
+
+    // unequal to the last value, if they're compared; kept for the next
+    fn is_new(&mut self, value: &T) -> bool {
+        let Distinct::Eq(eq, clone) = self.distinct else {
+            return true;
+        };
+        if self.last.as_ref().is_some_and(|last| eq(last, value)) {
+            return false;
+        }
+        self.last = Some(clone(value));
+        true
+    }
+}
+
+// the output of `future`, unless the token is cancelled first
+pub(crate) async fn cancellable<F: Future>(
+    cancel: Option<&CancellationToken>,
+    future: F,
+) -> Option<F::Output> {
+    let Some(cancel) = cancel else {
+        return Some(future.await);
+    };
+    match future::select(pin!(cancel.cancelled()), pin!(future)).await {
+        Either::Left(_) => None,
+        Either::Right((output, _)) => Some(output),
+    }
+}
+
+fn hash(body: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    hasher.finish()
+}
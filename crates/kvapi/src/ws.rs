@@ -0,0 +1,338 @@
+use crate::{retry::Backoff, watch::cancellable, Error, Result};
+use futures::{
+    stream::{self, BoxStream},
+    SinkExt, Stream, StreamExt,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{net::TcpStream, time::Instant};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tokio_util::sync::CancellationToken;
+
+type Connection = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// The WebSocket feed of an API, from `ws:` in `api!`.
+///
+/// ```rust,ignore
+/// ws: {
+///     url: "wss://stream.binance.com:9443/ws",
+///     subscribe: r#"{"method": "SUBSCRIBE", "params": ["{stream}"], "id": 1}"#,
+///     unsubscribe: r#"{"method": "UNSUBSCRIBE", "params": ["{stream}"], "id": 1}"#,
+///     ping: 3m,
+///     reconnect: exponential(500ms, 30s),
+///     streams: {
+///         "{symbol}@trade": Trade,
+///         "!miniTicker@arr": Vec<MiniTicker>,
+///     },
+/// }
+/// ```
+///
+/// Each stream's `subscribe()` opens its own connection. With a `subscribe` template, it's to
+/// the `url`, then the template is sent, with `{stream}` replaced by the stream's name; without
+/// one, it's to the stream's name under the `url`, i.e., `wss://stream.binance.com:9443/ws/btcusdt@trade`.
+///
+/// A `ping` is a Ping frame, or the given text, i.e., `ping: { every: 20s, message: r#"{"op": "ping"}"# }`;
+/// a connection that's heard nothing since the last one is dropped. A dropped connection is
+/// opened again after the `reconnect` backoff, by the failures to connect in a row, and
+/// subscribed again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Socket {
+    url: String,
+    subscribe: Option<&'static str>,
+    unsubscribe: Option<&'static str>,
+    ping: Option<(Duration, Option<&'static str>)>,
+    reconnect: Backoff,
+}
+
+impl Socket {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            subscribe: None,
+            unsubscribe: None,
+            ping: None,
+            reconnect: Backoff::exponential(Duration::from_millis(500), Duration::from_secs(30)),
+        }
+    }
+
+    /// Connect to `url` instead, i.e., a testnet.
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// The message sent once connected, to subscribe to `{stream}`.
+    pub fn subscribe_message(mut self, template: &'static str) -> Self {
+        self.subscribe = Some(template);
+        self
+    }
+
+    /// The message sent before closing, to unsubscribe from `{stream}`.
+    pub fn unsubscribe_message(mut self, template: &'static str) -> Self {
+        self.unsubscribe = Some(template);
+        self
+    }
+
+    /// Send a Ping frame `every` so often.
+    pub fn ping(mut self, every: Duration) -> Self {
+        self.ping = Some((every, None));
+        self
+    }
+
+    /// Send `message` as the ping, rather than a Ping frame; for APIs that expect their own.
+    pub fn ping_message(mut self, every: Duration, message: &'static str) -> Self {
+        self.ping = Some((every, Some(message)));
+        self
+    }
+
+    pub fn reconnect(mut self, backoff: Backoff) -> Self {
+        self.reconnect = backoff;
+        self
+    }
+
+    /// Subscribe to `stream`, decoding each message as a `T`; `endpoint` names it in any error.
+    pub fn subscribe<T>(&self, endpoint: &'static str, stream: String) -> Subscription<'_, T> {
+        Subscription {
+            socket: self,
+            endpoint,
+            name: stream,
+            strict: false,
+            cancel: None,
+            stream: None,
+            _data: PhantomData,
+        }
+    }
+
+    // a new connection, subscribed to `stream`
+    async fn connect(&self, stream: &str) -> Result<Connection> {
+        let url = match self.subscribe {
+            Some(_) => self.url.clone(),
+            None => format!("{}/{stream}", self.url.trim_end_matches('/')),
+        };
+        let (mut connection, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(Error::websocket)?;
+        if let Some(subscribe) = self.subscribe {
+            let message = Message::Text(render(subscribe, stream));
+            connection.send(message).await.map_err(Error::websocket)?;
+        }
+        Ok(connection)
+    }
+}
+
+/// The messages of a stream, from its `subscribe()`; a `Stream` of each decoded as the stream's
+/// type.
+///
+/// ```rust,ignore
+/// let token = kvapi::CancellationToken::new();
+/// let mut trades = binance.ws.trade.subscribe("btcusdt").until(token.clone());
+///
+/// while let Some(trade) = trades.next().await { ... }
+/// ```
+///
+/// It connects when it's first polled, and never ends on its own; a failed connection is
+/// yielded as an `Error::WebSocket`, then opened again. Control messages (acknowledgements,
+/// heartbeats, pongs) rarely share a stream's type, so a message that doesn't decode as one is
+/// skipped, unless it's `strict()`.
+pub struct Subscription<'a, T> {
+    socket: &'a Socket,
+    endpoint: &'static str,
+    name: String,
+    strict: bool,
+    cancel: Option<CancellationToken>,
+    stream: Option<BoxStream<'a, Result<T>>>,
+    _data: PhantomData<fn() -> T>,
+}
+
+// what's left of `Subscription`, once it's polled
+struct State<'a, T> {
+    socket: &'a Socket,
+    endpoint: &'static str,
+    name: String,
+    strict: bool,
+    cancel: Option<CancellationToken>,
+    connection: Option<Connection>,
+    failures: u32, // in a row
+    ping: Option<Instant>,
+    heard: bool, // since the last ping
+    _data: PhantomData<fn() -> T>,
+}
+
+impl<'a, T> Subscription<'a, T> {
+    /// Yield a message that doesn't decode as an `Error::Decode`, rather than skip it.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Unsubscribe, and end the stream, once `token` is cancelled.
+    pub fn until(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+}
+
+impl<'a, T> Stream for Subscription<'a, T>
+where
+    T: DeserializeOwned + Send + 'a,
+{
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.stream.is_none() {
+            let state = State {
+                socket: self.socket,
+                endpoint: self.endpoint,
+                name: std::mem::take(&mut self.name),
+                strict: self.strict,
+                cancel: self.cancel.take(),
+                connection: None,
+                failures: 0,
+                ping: None,
+                heard: true,
+                _data: PhantomData,
+            };
+            self.stream = Some(stream::unfold(state, State::next).boxed());
+        }
+        self.stream.as_mut().unwrap().poll_next_unpin(cx)
+    }
+}
+
+impl<'a, T> State<'a, T>
+where
+    T: DeserializeOwned + Send + 'a,
+{
+    // the next message (or failure) to yield; none once cancelled
+    async fn next(mut self) -> Option<(Result<T>, Self)> {
+        let cancel = self.cancel.clone();
+        loop {
+            let Some(connection) = self.connection.as_mut() else {
+                if self.failures > 0 {
+                    let wait = self.socket.reconnect.delay(self.failures);
+                    cancellable(cancel.as_ref(), tokio::time::sleep(wait)).await?;
+                }
+                let connect = self.socket.connect(&self.name);
+                match cancellable(cancel.as_ref(), connect).await? {
+                    Ok(connection) => self.connected(connection),
+                    Err(e) => return Some((Err(self.dropped(e)), self)),
+                }
+                continue;
+            };
+
+            // the next message, unless a ping is due first
+            let ping = self.ping;
+            let read = async {
+                match ping {
+                    Some(ping) => tokio::time::timeout_at(ping, connection.next()).await.ok(),
+                    None => Some(connection.next().await),
+                }
+            };
+            let Some(message) = cancellable(cancel.as_ref(), read).await else {
+                self.close().await;
+                return None;
+            };
+
+            let message = match message {
+                Some(Some(Ok(message))) => message,
+                Some(Some(Err(e))) => return Some((Err(self.dropped(Error::websocket(e))), self)),
+                // closed by the server; opened again, as any other drop, but quietly
+                Some(None) => {
+                    self.dropped(Error::websocket("closed"));
+                    continue;
+                }
+                None => match self.heard {
+                    true => {
+                        if let Err(e) = self.send_ping().await {
+                            return Some((Err(self.dropped(e)), self));
+                        }
+                        continue;
+                    }
+                    false => {
+                        let error = Error::websocket("no answer to the last ping");
+                        return Some((Err(self.dropped(error)), self));
+                    }
+                },
+            };
+
+            self.heard = true;
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Binary(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Message::Close(_) => {
+                    self.dropped(Error::websocket("closed"));
+                    continue;
+                }
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => continue,
+            };
+            let mut de = serde_json::Deserializer::from_str(&text);
+            match serde_path_to_error::deserialize::<_, T>(&mut de) {
+                Ok(value) => return Some((Ok(value), self)),
+                Err(_) if !self.strict => continue,
+                Err(e) => {
+                    let path = e.path().to_string();
+                    let error = Error::decode(self.endpoint, path, e.into_inner(), text);
+                    return Some((Err(error), self));
+                }
+            }
+        }
+    }
+
+    // connected & subscribed; the backoff starts over, whatever the connection carries
+    fn connected(&mut self, connection: Connection) {
+        self.connection = Some(connection);
+        self.failures = 0;
+        self.ping = self.socket.ping.map(|(every, _)| Instant::now() + every);
+        self.heard = true;
+    }
+
+    // the connection is gone; it's opened again after the backoff
+    fn dropped(&mut self, error: Error) -> Error {
+        self.connection = None;
+        self.failures += 1;
+        error
+    }
+
+    async fn send_ping(&mut self) -> Result<()> {
+        let (Some(connection), Some((every, message))) = (&mut self.connection, self.socket.ping)
+        else {
+            return Ok(());
+        };
+        let ping = match message {
+            Some(message) => Message::Text(message.to_string()),
+            None => Message::Ping(vec![]),
+        };
+        connection.send(ping).await.map_err(Error::websocket)?;
+        self.ping = Some(Instant::now() + every);
+        self.heard = false;
+        Ok(())
+    }
+
+    // unsubscribe, then close the connection; as best it can, without waiting long
+    async fn close(&mut self) {
+        let Some(mut connection) = self.connection.take() else {
+            return;
+        };
+        let unsubscribe = self.socket.unsubscribe.map(|t| render(t, &self.name));
+        let close = async move {
+            if let Some(unsubscribe) = unsubscribe {
+                connection.send(Message::Text(unsubscribe)).await.ok();
+            }
+            connection.close(None).await.ok();
+        };
+        tokio::time::timeout(Duration::from_secs(1), close)
+            .await
+            .ok();
+    }
+}
+
+// a message template, with `{stream}` replaced; escaped, as it's most likely within a JSON string
+fn render(template: &str, stream: &str) -> String {
+    let escaped = serde_json::to_string(stream).unwrap_or_default();
+    let escaped = escaped.trim_matches('"');
+    template.replace("{stream}", escaped)
+}
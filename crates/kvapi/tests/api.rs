@@ -0,0 +1,2060 @@
+// Test the code generated by `api!`, from `kvapi_macros` crate.
+//
+// Note: the generated structs live in this module, so their private functions are accessible.
+
+mod common;
+
+use common::{Response, Server};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+kvapi::api! {
+    name:   Example
+    base:   "https://example.com/"
+    query:  "key=secret"
+    dict:   {
+                "series/{series_id}/observations": Value,
+                "users/{id: u32}/posts.json": Value,
+
+                #[rename: "files", query: "?download=true"]
+                "files/{path}": Value,
+
+                #[query: "key=override&limit=10"]
+                "overrides?limit=5&sort=asc": Value,
+
+                #[rename: "price"]
+                "ticker/price?symbol={symbol}": Value,
+
+                #[params(
+                    series_id: &str,
+                    limit: Option<u32>,
+                    sort_order: Option<SortOrder>,
+                    symbols: Option<Vec<&str>> as json,
+                    ids: Option<Vec<u32>> as comma,
+                    tags: Option<Vec<&str>>,
+                    startTime: Option<u64>,
+                )]
+                "releases/dates": Value,
+            }
+}
+
+kvapi::api! {
+    name:   NoBase
+    dict:   { "not/absolute": Value }
+}
+
+// `{host}` lets these point at a local `Server`
+kvapi::api! {
+    name:   Orders
+    dict:   {
+                #[rename: "order", method: [GET, DELETE]]
+                "http://{host}/order/{id: u32}": Value,
+
+                #[rename: "settings", method(PUT, PATCH, HEAD)]
+                "http://{host}/settings": Value,
+
+                #[rename: "create", method: POST]
+                "http://{host}/orders": CreateOrder => OrderAck,
+
+                #[rename: "token", method: POST, body: form]
+                "http://{host}/token": TokenForm => Value,
+
+                #[rename: "upload", method: PUT, body: bytes]
+                "http://{host}/upload/{name}": Vec<u8> => Value,
+            }
+}
+
+kvapi::api! {
+    name:       Keyed
+    headers:    { "X-Api-Key": "abc" }
+    dict:       {
+                    #[rename: "ping"]
+                    "http://{host}/ping": Value,
+
+                    #[rename: "pong"]
+                    "http://{host}/pong": Value,
+                }
+}
+
+kvapi::api! {
+    name:       Unset
+    headers:    { "X-Token": &std::env::var("KVAPI_TEST_UNSET_TOKEN")? }
+    dict:       { #[rename: "unset"] "https://example.com/unset": Value }
+}
+
+kvapi::api! {
+    name:       Newline
+    headers:    { "X-Token": "a\nb" }
+    dict:       { #[rename: "newline"] "https://example.com/newline": Value }
+}
+
+kvapi::api! {
+    name:       Failing
+    dict:       {
+                    #[rename: "status"]
+                    "http://{host}/status/{code: u16}": Value,
+
+                    #[rename: "schema"]
+                    "http://{host}/schema": Vec<Observation>,
+                }
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Observation {
+    date: String,
+    value: f64,
+}
+
+kvapi::api! {
+    name:       Exchange
+    error:      ExchangeError
+    dict:       {
+                    #[rename: "price", params(symbol: &str)]
+                    "http://{host}/ticker/price": Value,
+                }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct ExchangeError {
+    code: i64,
+    msg: String,
+}
+
+// `{"error": [...], "result": ...}`
+kvapi::api! {
+    name:       Wrapped
+    envelope:   { data: "result", error: "error" }
+    dict:       {
+                    #[rename: "time", params(case: Option<&str>)]
+                    "http://{host}/time": Time,
+
+                    // an entry's envelope replaces the API's
+                    #[rename: "dates", envelope(data: "page/items"), params(case: Option<&str>)]
+                    "http://{host}/dates": Vec<String>,
+                }
+}
+
+// `{"code": "200000", "data": ...}`
+kvapi::api! {
+    name:       Coded
+    error:      CodedError
+    envelope:   { data: "data", success: "code" == "200000" }
+    dict:       {
+                    #[rename: "currencies", params(case: Option<&str>)]
+                    "http://{host}/currencies": Vec<String>,
+                }
+}
+
+// by extension, or `#[format]`; the API's envelope only opens JSON
+kvapi::api! {
+    name:       Formats
+    envelope:   { data: "data" }
+    dict:       {
+                    #[rename: "rates", params(case: Option<&str>)]
+                    "http://{host}/rates.csv": Vec<Rate>,
+
+                    #[rename: "feed", format: xml]
+                    "http://{host}/feed": Feed,
+
+                    #[rename: "config"]
+                    "http://{host}/config.yaml": Config,
+
+                    #[rename: "manifest"]
+                    "http://{host}/manifest.toml": Config,
+
+                    #[rename: "status"]
+                    "http://{host}/status.json": Config,
+                }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Rate {
+    date: String,
+    value: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Feed {
+    title: String,
+    entry: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Config {
+    name: String,
+    version: u32,
+}
+
+// the host is rewritten by a middleware, at runtime
+kvapi::api! {
+    name:       Intercepted
+    middleware: [Stamp::new("X-Correlation-Id", "42"), Stamp::new("X-Trace", "on")]
+    dict:       {
+                    #[rename: "echo", method(GET, POST)]
+                    "http://unreachable.invalid/echo": Value,
+                }
+}
+
+// sets a request header before, and notes it in the response after
+struct Stamp(&'static str, &'static str);
+
+impl Stamp {
+    fn new(name: &'static str, value: &'static str) -> Self {
+        Self(name, value)
+    }
+}
+
+#[kvapi::async_trait]
+impl kvapi::Middleware for Stamp {
+    async fn before(&self, request: &mut kvapi::Request) -> kvapi::Result<()> {
+        let value = kvapi::HeaderValue::from_static(self.1);
+        request.headers_mut().insert(self.0, value);
+        Ok(())
+    }
+
+    async fn after(
+        &self,
+        mut response: kvapi::reqwest::Response,
+    ) -> kvapi::Result<kvapi::reqwest::Response> {
+        let seen = match response.headers().get("X-Seen") {
+            Some(seen) => format!("{},{}", seen.to_str().unwrap(), self.0),
+            None => self.0.to_string(),
+        };
+        response
+            .headers_mut()
+            .insert("X-Seen", seen.parse().unwrap());
+        Ok(response)
+    }
+}
+
+// points the request at `host`, as a PUT, with an uppercased body
+struct Rewrite(String);
+
+#[kvapi::async_trait]
+impl kvapi::Middleware for Rewrite {
+    async fn before(&self, request: &mut kvapi::Request) -> kvapi::Result<()> {
+        let (host, port) = self.0.split_once(':').unwrap();
+        let url = request.url_mut();
+        url.set_host(Some(host)).map_err(kvapi::Error::middleware)?;
+        url.set_port(port.parse().ok())
+            .map_err(|_| kvapi::Error::middleware("invalid port"))?;
+        if request.method() == kvapi::Method::POST {
+            *request.method_mut() = kvapi::Method::PUT;
+        }
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(<[u8]>::to_ascii_uppercase);
+        if let Some(body) = body {
+            *request.body_mut() = Some(body.into());
+        }
+        Ok(())
+    }
+}
+
+struct Deny;
+
+#[kvapi::async_trait]
+impl kvapi::Middleware for Deny {
+    async fn before(&self, _: &mut kvapi::Request) -> kvapi::Result<()> {
+        Err(kvapi::Error::middleware("denied"))
+    }
+}
+
+// adds `tag=1` to the query
+struct Tag;
+
+#[kvapi::async_trait]
+impl kvapi::Middleware for Tag {
+    async fn before(&self, request: &mut kvapi::Request) -> kvapi::Result<()> {
+        request.url_mut().query_pairs_mut().append_pair("tag", "1");
+        Ok(())
+    }
+}
+
+kvapi::api! {
+    name:       Signed
+    middleware: [kvapi::signer::Binance::new("api-key", "secret")]
+    dict:       {
+                    #[rename: "order", params(symbol: &str), method(GET, POST)]
+                    "http://{host}/api/v3/order": Value,
+                }
+}
+
+kvapi::api! {
+    name:       Tokened
+    auth:       Bearer("t0ken")
+    dict:       { #[rename: "me"] "http://{host}/me": Value }
+}
+
+kvapi::api! {
+    name:       Logged
+    auth:       Basic("user", &String::from("pass"))
+    dict:       { #[rename: "me"] "http://{host}/me": Value }
+}
+
+kvapi::api! {
+    name:       QueryKeyed
+    query:      "file_type=json"
+    auth:       ApiKey { in: query, name: "api_key", from: env "KVAPI_TEST_API_KEY" }
+    dict:       {
+                    #[rename: "series", params(series_id: &str)]
+                    "http://{host}/series": Value,
+                }
+}
+
+kvapi::api! {
+    name:       Anonymous
+    auth:       Bearer(env "KVAPI_TEST_UNSET_TOKEN")
+    dict:       { #[rename: "me"] "http://{host}/me": Value }
+}
+
+// the token endpoint is a local `Server`
+kvapi::api! {
+    name:       Delegated
+    auth:       OAuth2 {
+                    token_url: env "KVAPI_TEST_TOKEN_URL",
+                    client_id: "id",
+                    client_secret: "s3cret",
+                    scopes: ["read", "write"],
+                }
+    dict:       {
+                    #[rename: "data"]
+                    "http://{host}/data": Value,
+
+                    #[rename: "strict"]
+                    "http://{host}/strict": Value,
+                }
+}
+
+kvapi::api! {
+    name:       Refreshed
+    auth:       OAuth2 {
+                    token_url: env "KVAPI_TEST_TOKEN_URL",
+                    client_id: "id",
+                    client_secret: "s3cret",
+                    refresh_token: "r0",
+                }
+    dict:       { #[rename: "data"] "http://{host}/data": Value }
+}
+
+kvapi::api! {
+    name:       Throttled
+    rate_limit: 10/sec
+    dict:       {
+                    #[rename: "ping"]
+                    "http://{host}/ping": Value,
+
+                    #[rename: "depth", weight: 5]
+                    "http://{host}/depth": Value,
+                }
+}
+
+kvapi::api! {
+    name:       Neighbour
+    dict:       {
+                    #[rename: "depth", weight: 5]
+                    "http://{host}/depth": Value,
+                }
+}
+
+kvapi::api! {
+    name:       Retried
+    retry:      { attempts: 3, backoff: constant(10ms) }
+    dict:       {
+                    #[rename: "flaky"]
+                    "http://{host}/flaky": Value,
+
+                    #[rename: "once", retry(attempts: 1)]
+                    "http://{host}/once": Value,
+
+                    #[rename: "orders", retry(attempts: 3, backoff: constant(10ms), non_idempotent: true)]
+                    "http://{host}/orders": Value,
+
+                    #[rename: "limited", retry(attempts: 2, on: [429])]
+                    "http://{host}/limited": Value,
+
+                    #[rename: "busy", retry(attempts: 3, max_wait: 2s)]
+                    "http://{host}/busy": Value,
+                }
+}
+
+kvapi::api! {
+    name:               Slow
+    timeout:            300ms
+    connect_timeout:    1s
+    dict:               {
+                            #[rename: "report", params(delay: u64)]
+                            "http://{host}/report": Value,
+
+                            #[rename: "ticker", params(delay: u64), timeout: 50ms]
+                            "http://{host}/ticker": Value,
+
+                            #[rename: "bulk", timeout: 50ms, retry(attempts: 2, on: [timeout])]
+                            "http://{host}/bulk": Value,
+                        }
+}
+
+kvapi::api! {
+    name:       Cached
+    cache:      200ms
+    dict:       {
+                    #[rename: "quote", method(GET, POST)]
+                    "http://{host}/quote": Value,
+
+                    #[rename: "tickers", cache: 1h]
+                    "http://{host}/tickers": Value,
+                }
+}
+
+kvapi::api! {
+    name:       Persisted
+    cache:      { store: kvapi::cache::Disk::new(std::env::temp_dir().join(format!("kvapi-cache-{}", std::process::id()))) }
+    dict:       {
+                    #[rename: "tickers", cache: 1h]
+                    "http://{host}/tickers": Value,
+
+                    #[rename: "quote"]
+                    "http://{host}/quote": Value,
+                }
+}
+
+kvapi::api! {
+    name:       Paged
+    dict:       {
+                    #[rename: "series", params(category_id: u32)]
+                    #[paginate(offset = "offset", limit = "limit", size = 2, total = "count", items = "seriess", item = Series)]
+                    "http://{host}/category/series": CategorySeries,
+
+                    #[rename: "ignored", paginate(offset = "offset", item = u32)]
+                    "http://{host}/ignored": Vec<u32>,
+
+                    #[rename: "trades", paginate(cursor = "cursor", next = "meta/next", items = "data")]
+                    "http://{host}/trades": Value,
+
+                    #[rename: "history", paginate(link, item = u32)]
+                    "http://{host}/history": Vec<u32>,
+
+                    #[rename: "broken", paginate(link, item = u32)]
+                    "http://{host}/broken": Vec<u32>,
+                }
+}
+
+kvapi::api! {
+    name:       Batched
+    rate_limit: 10/sec
+    dict:       {
+                    #[rename: "price", params(symbol: &str, window: Option<u32>)]
+                    "http://{host}/price": Value,
+
+                    #[rename: "series", params(series_id: &str)]
+                    "http://{host}/category/{category_id: u32}/series": Value,
+                }
+}
+
+kvapi::api! {
+    name:       Watched
+    dict:       { #[rename: "ticker"] "http://{host}/ticker/{feed}": Ticker }
+}
+
+kvapi::api! {
+    name:       Streamed
+    dict:       { "ping": Value }
+    ws:         {
+                    url: "ws://127.0.0.1:9/ws",
+                    subscribe: r#"{"method": "SUBSCRIBE", "params": ["{stream}"]}"#,
+                    unsubscribe: r#"{"method": "UNSUBSCRIBE", "params": ["{stream}"]}"#,
+                    ping: 50ms,
+                    reconnect: constant(10ms),
+                    streams: {
+                        "{symbol}@ticker": Ticker,
+                        "!ticker@arr": Vec<Ticker>,
+                    },
+                }
+}
+
+kvapi::api! {
+    name:       Raw
+    dict:       { "ping": Value }
+    ws:         {
+                    url: "ws://127.0.0.1:9/ws/",
+                    ping: { every: 20ms, message: "ping" },
+                    reconnect: constant(10ms),
+                    streams: { "{symbol}@ticker": Ticker },
+                }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct CategorySeries {
+    count: u32,
+    seriess: Vec<Series>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Series {
+    id: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+struct Ticker {
+    price: u32,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Time {
+    unixtime: u64,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct CodedError {
+    code: String,
+    msg: String,
+}
+
+#[derive(Serialize)]
+struct CreateOrder {
+    symbol: &'static str,
+    qty: u32,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct OrderAck {
+    id: u64,
+}
+
+#[derive(Serialize)]
+struct TokenForm {
+    grant_type: &'static str,
+    scope: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    Desc,
+}
+
+fn url(builder: kvapi::url::UrlBuilder) -> String {
+    builder.build().expect("valid URL").to_string()
+}
+
+// path parameters
+#[test]
+fn path_params() {
+    let api = Example::new();
+
+    // substituted in order, without becoming fields
+    assert_eq!(
+        url(api.series.observations.url("DGS10")),
+        "https://example.com/series/DGS10/observations?key=secret"
+    );
+    assert_eq!(
+        url(api.users.posts.url(7)),
+        "https://example.com/users/7/posts.json?key=secret"
+    );
+
+    // percent-encoded, and never able to escape their segment
+    assert_eq!(
+        url(api.files.url("reports/2024 Q1.csv?")),
+        "https://example.com/files/reports%2F2024%20Q1.csv%3F?key=secret&download=true"
+    );
+    for dots in [".", ".."] {
+        let error = api.series.observations.url(dots).build().unwrap_err();
+        assert!(matches!(error, kvapi::Error::Url(_)), "{error}");
+    }
+    assert_eq!(
+        url(api.series.observations.url("%2e%2e")),
+        "https://example.com/series/%252e%252e/observations?key=secret"
+    );
+
+    // after the `?`, a query value; never able to add a parameter
+    let price = url(api.price.url("BTC&symbol=ETH+1=2"));
+    assert_eq!(
+        price,
+        "https://example.com/ticker/price?key=secret&symbol=BTC%26symbol%3DETH%2B1%3D2"
+    );
+}
+
+// query parameters
+#[tokio::test]
+async fn query_params() {
+    let api = Example::new();
+
+    // optional params are left out when unset
+    let request = api.releases.dates.request().series_id("DGS10");
+    assert_eq!(
+        request.url().unwrap().as_str(),
+        "https://example.com/releases/dates?key=secret&series_id=DGS10"
+    );
+
+    // setters encode each value, in the order they are called
+    let request = api
+        .releases
+        .dates
+        .request()
+        .series_id("DGS 10")
+        .limit(100)
+        .sort_order(SortOrder::Desc)
+        .start_time(1700000000000)
+        .symbols(vec!["BTCUSDT", "BNBBTC"])
+        .ids(vec![1, 2])
+        .tags(vec!["a", "b"]);
+    assert_eq!(
+        request.url().unwrap().as_str(),
+        "https://example.com/releases/dates?key=secret&series_id=DGS+10&limit=100&sort_order=desc\
+         &startTime=1700000000000&symbols=%5B%22BTCUSDT%22%2C%22BNBBTC%22%5D&ids=1%2C2&tags=a&tags=b"
+    );
+
+    // required params must be set before sending
+    let error = api.releases.dates.request().limit(1).get().await;
+    assert!(error.unwrap_err().to_string().contains("`series_id`"));
+}
+
+// url composition
+#[tokio::test]
+async fn url_composition() {
+    use kvapi::url::UrlBuilder;
+
+    // exactly one slash between segments
+    for (base, path) in [
+        ("https://example.com/api", "v1/ping"),
+        ("https://example.com/api/", "v1/ping"),
+        ("https://example.com/api", "/v1/ping"),
+        ("https://example.com/api/", "/v1/ping"),
+    ] {
+        let builder = UrlBuilder::new().base(base).path(path);
+        assert_eq!(url(builder), "https://example.com/api/v1/ping");
+    }
+
+    // query levels: global < endpoint < `#[query]` < per-call; overridden keys move to the end
+    let api = Example::new();
+    assert_eq!(
+        url(api.overrides.url()),
+        "https://example.com/overrides?sort=asc&key=override&limit=10"
+    );
+    let builder = api.overrides.url().query("sort=desc&extra=a+b");
+    assert_eq!(
+        url(builder),
+        "https://example.com/overrides?key=override&limit=10&sort=desc&extra=a+b"
+    );
+
+    // malformed urls are rejected before sending
+    let error = NoBase::new().not.absolute.get().await.unwrap_err();
+    assert!(error.to_string().contains("malformed URL `not/absolute`"));
+    let error = UrlBuilder::new()
+        .base("ftp://example.com")
+        .build()
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("expected an `http` or `https` URL"));
+}
+
+// http methods
+#[tokio::test]
+async fn http_methods() {
+    let server =
+        Server::start(|_| Response::json(200, json!({ "ok": true })).header("X-Mock", "1")).await;
+    let (host, api) = (server.host(), Orders::new());
+
+    // only the declared methods exist, e.g., `api.order.post(..)` would not compile
+    assert_eq!(
+        api.order.get(&host, 7).await.unwrap(),
+        json!({ "ok": true })
+    );
+    assert_eq!(
+        api.order.delete(&host, 7).await.unwrap(),
+        json!({ "ok": true })
+    );
+    api.settings.put(&host, &json!({ "a": 1 })).await.unwrap();
+    api.settings.patch(&host, &json!({ "b": 2 })).await.unwrap();
+    let headers = api.settings.head(&host).await.unwrap();
+    assert_eq!(headers["X-Mock"], "1");
+
+    let requests = server
+        .requests()
+        .into_iter()
+        .map(|request| format!("{} {} {}", request.method, request.target, request.body()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        requests,
+        vec![
+            "GET /order/7 ",
+            "DELETE /order/7 ",
+            "PUT /settings {\"a\":1}",
+            "PATCH /settings {\"b\":2}",
+            "HEAD /settings ",
+        ]
+    );
+}
+
+// typed request bodies
+#[tokio::test]
+async fn request_bodies() {
+    let server = Server::start(|_| Response::json(200, json!({ "id": 42 }))).await;
+    let (host, api) = (server.host(), Orders::new());
+
+    let order = CreateOrder {
+        symbol: "BTCUSDT",
+        qty: 2,
+    };
+    let ack = api.create.post(&host, &order).await.unwrap();
+    assert_eq!(ack, OrderAck { id: 42 });
+
+    let form = TokenForm {
+        grant_type: "client_credentials",
+        scope: "read write",
+    };
+    api.token.post(&host, &form).await.unwrap();
+    api.upload
+        .put(&host, "a b", &vec![0, 159, 146, 150])
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    let content_types = requests
+        .iter()
+        .map(|request| request.header("content-type").unwrap_or_default())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        content_types,
+        vec![
+            "application/json",
+            "application/x-www-form-urlencoded",
+            "application/octet-stream",
+        ]
+    );
+    assert_eq!(requests[0].body(), r#"{"symbol":"BTCUSDT","qty":2}"#);
+    assert_eq!(
+        requests[1].body(),
+        "grant_type=client_credentials&scope=read+write"
+    );
+    assert_eq!(requests[2].target, "/upload/a%20b");
+    assert_eq!(requests[2].body, vec![0, 159, 146, 150]);
+}
+
+// one client per API
+#[tokio::test]
+async fn shared_client() {
+    let server = Server::start(|_| Response::json(200, json!({}))).await;
+    let host = server.host();
+
+    // every endpoint borrows the root's client
+    let api = Keyed::new();
+    assert!(std::ptr::eq(api.client(), api.ping.client()));
+    assert!(std::ptr::eq(api.ping.client(), api.pong.client()));
+
+    // a given client is used as is, and the API's headers are still sent
+    let mut headers = kvapi::HeaderMap::new();
+    headers.insert("X-Custom", kvapi::HeaderValue::from_static("1"));
+    let client = kvapi::ClientBuilder::new()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+    let api = Keyed::with_client(client);
+    api.ping.get(&host).await.unwrap();
+    api.pong.get(&host).await.unwrap();
+
+    for request in server.requests() {
+        assert_eq!(request.header("X-Api-Key"), Some("abc"));
+        assert_eq!(request.header("X-Custom"), Some("1"));
+    }
+    assert_eq!(server.requests().len(), 2);
+}
+
+// fallible construction
+#[test]
+fn try_new() {
+    let error = Unset::try_new().err().unwrap();
+    assert_eq!(
+        format!("{:#}", error),
+        "failed to build `Unset`: invalid header `X-Token`: environment variable not found"
+    );
+
+    let error = Newline::try_new().err().unwrap();
+    assert!(
+        format!("{:#}", error).starts_with("failed to build `Newline`: invalid header `X-Token`: ")
+    );
+
+    let error = Newline::try_with_client(kvapi::Client::new())
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("`Newline`"));
+
+    // `new()` panics with the same message
+    let panic = std::panic::catch_unwind(Unset::new).err().unwrap();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(message.starts_with("failed to build `Unset`"));
+}
+
+// structured errors
+#[tokio::test]
+async fn errors() {
+    let server = Server::start(|request| match request.target.as_str() {
+        "/schema" => Response::json(
+            200,
+            json!([{ "date": "2024-01-01", "value": 1.5 }, { "date": "2024-01-02", "value": "." }]),
+        ),
+        target => {
+            let code = target.trim_start_matches("/status/").parse().unwrap();
+            Response::new(code, format!("error {code}"))
+        }
+    })
+    .await;
+    let (host, api) = (server.host(), Failing::new());
+
+    // status
+    let error = api.status.get(&host, 429).await.unwrap_err();
+    assert!(matches!(error, kvapi::Error::Status { .. }));
+    assert_eq!(error.status(), Some(kvapi::StatusCode::TOO_MANY_REQUESTS));
+    assert_eq!(error.body(), Some("error 429"));
+    assert!(error.is_rate_limited() && error.is_retryable());
+
+    let error = api.status.get(&host, 503).await.unwrap_err();
+    assert!(!error.is_rate_limited() && error.is_retryable());
+
+    let error = api.status.get(&host, 404).await.unwrap_err();
+    assert!(!error.is_retryable());
+    assert!(error
+        .to_string()
+        .starts_with("HTTP 404 Not Found from `http://"));
+
+    // decode; names the endpoint & the path to the error
+    let error = api.schema.get(&host).await.unwrap_err();
+    assert!(error.is_decode() && !error.is_retryable());
+    let kvapi::Error::Decode { endpoint, path, .. } = &error else {
+        panic!("expected a decode error, found {error:?}");
+    };
+    assert_eq!(*endpoint, "http://{host}/schema");
+    assert_eq!(path, "[1].value");
+    assert!(error.body().unwrap().contains(r#""value":".""#));
+
+    // transport
+    let closed = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    };
+    let error = api.status.get(&closed, 200).await.unwrap_err();
+    assert!(matches!(error, kvapi::Error::Transport(_)));
+    assert!(error.is_retryable());
+
+    // url
+    let error = NoBase::new().not.absolute.get().await.unwrap_err();
+    assert!(matches!(error, kvapi::Error::Url(_)));
+    assert!(!error.is_retryable());
+}
+
+// the API's own error type
+#[tokio::test]
+async fn api_errors() {
+    let server = Server::start(|request| match request.target.as_str() {
+        "/ticker/price?symbol=BNB" => {
+            Response::json(400, json!({ "code": -1121, "msg": "Invalid symbol." }))
+        }
+        "/ticker/price?symbol=BTCUSDT" => Response::json(200, json!({ "price": "1" })),
+        _ => Response::new(502, "<html>Bad Gateway</html>"),
+    })
+    .await;
+    let (host, api) = (server.host(), Exchange::new());
+
+    // decoded into `ExchangeError`
+    let error = api
+        .price
+        .request(&host)
+        .symbol("BNB")
+        .get()
+        .await
+        .unwrap_err();
+    let expected = ExchangeError {
+        code: -1121,
+        msg: "Invalid symbol.".to_string(),
+    };
+    assert_eq!(error.api::<ExchangeError>(), Some(&expected));
+    assert_eq!(error.status(), Some(kvapi::StatusCode::BAD_REQUEST));
+    assert!(!error.is_retryable());
+    let kvapi::Error::Api(e) = &error else {
+        panic!("expected an API error, found {error:?}");
+    };
+    assert!(e.is::<ExchangeError>());
+    assert_eq!(e.body, r#"{"code":-1121,"msg":"Invalid symbol."}"#);
+    assert!(error.to_string().contains("Invalid symbol."));
+
+    // successes are unaffected
+    let price = api
+        .price
+        .request(&host)
+        .symbol("BTCUSDT")
+        .get()
+        .await
+        .unwrap();
+    assert_eq!(price, json!({ "price": "1" }));
+
+    // any other body is kept as is
+    let error = api
+        .price
+        .request(&host)
+        .symbol("ETH")
+        .get()
+        .await
+        .unwrap_err();
+    assert!(matches!(error, kvapi::Error::Status { .. }));
+    assert_eq!(error.body(), Some("<html>Bad Gateway</html>"));
+    assert_eq!(error.api::<ExchangeError>(), None);
+    assert!(error.is_retryable());
+}
+
+// response envelopes
+#[tokio::test]
+async fn envelopes() {
+    let server = Server::start(|request| match request.target.as_str() {
+        "/time" => Response::json(
+            200,
+            json!({ "error": [], "result": { "unixtime": 1688669448 } }),
+        ),
+        "/dates" => Response::json(
+            200,
+            json!({ "page": { "items": ["2024-01-01"], "next": null } }),
+        ),
+        "/time?case=fail" => Response::json(
+            200,
+            json!({ "error": ["EGeneral:Invalid arguments"], "result": {} }),
+        ),
+        "/dates?case=fail" => Response::json(200, json!({ "page": { "items": [1] } })),
+        "/currencies" => Response::json(200, json!({ "code": "200000", "data": ["BTC", "ETH"] })),
+        "/currencies?case=fail" => {
+            Response::json(200, json!({ "code": "400100", "msg": "Invalid parameter" }))
+        }
+        _ => Response::json(200, json!({ "code": 400100 })),
+    })
+    .await;
+    let host = server.host();
+
+    // the data is unwrapped
+    let api = Wrapped::new();
+    assert_eq!(
+        api.time.get(&host).await.unwrap(),
+        Time {
+            unixtime: 1688669448
+        }
+    );
+    assert_eq!(api.dates.get(&host).await.unwrap(), vec!["2024-01-01"]);
+
+    let api = Coded::new();
+    assert_eq!(api.currencies.get(&host).await.unwrap(), vec!["BTC", "ETH"]);
+
+    // failures reported in the envelope, despite the HTTP 200
+    let api = Wrapped::new();
+    let error = api
+        .time
+        .request(&host)
+        .case("fail")
+        .get()
+        .await
+        .unwrap_err();
+    let kvapi::Error::Envelope {
+        endpoint,
+        error: envelope,
+        ..
+    } = &error
+    else {
+        panic!("expected an envelope error, found {error:?}");
+    };
+    assert_eq!(*endpoint, "http://{host}/time");
+    assert_eq!(
+        *envelope,
+        json!({ "error": ["EGeneral:Invalid arguments"] })
+    );
+    assert!(!error.is_retryable());
+
+    // decoded into the API's error type, if possible
+    let api = Coded::new();
+    let error = api
+        .currencies
+        .request(&host)
+        .case("fail")
+        .get()
+        .await
+        .unwrap_err();
+    let expected = CodedError {
+        code: "400100".to_string(),
+        msg: "Invalid parameter".to_string(),
+    };
+    assert_eq!(error.api::<CodedError>(), Some(&expected));
+    assert_eq!(error.status(), Some(kvapi::StatusCode::OK));
+    assert_eq!(
+        error.body(),
+        Some(r#"{"code":"400100","msg":"Invalid parameter"}"#)
+    );
+    let error = api
+        .currencies
+        .request(&host)
+        .case("other")
+        .get()
+        .await
+        .unwrap_err();
+    assert!(matches!(error, kvapi::Error::Envelope { .. }));
+
+    // decode errors point into the data
+    let api = Wrapped::new();
+    let error = api
+        .dates
+        .request(&host)
+        .case("fail")
+        .get()
+        .await
+        .unwrap_err();
+    let kvapi::Error::Decode { path, .. } = &error else {
+        panic!("expected a decode error, found {error:?}");
+    };
+    assert_eq!(path, "page.items[0]");
+}
+
+#[tokio::test]
+async fn formats() {
+    let server = Server::start(|request| match request.target.as_str() {
+        "/rates.csv" => Response::new(200, "date,value\n2024-01-01,1.5\n2024-01-02,\n"),
+        "/rates.csv?case=fail" => Response::new(200, "date,value\n2024-01-01,1.5\n2024-01-02,.\n"),
+        "/rates.csv?case=short" => Response::new(200, "date,value\n2024-01-01,1.5\n2024-01-02\n"),
+        "/rates.csv?case=long" => Response::new(200, "date,value\n2024-01-01,1.5,2\n"),
+        "/feed" => Response::new(
+            200,
+            "<feed><title>News</title><entry>one</entry><entry>two</entry></feed>",
+        ),
+        "/config.yaml" => Response::new(200, "name: kvapi\nversion: 1\n"),
+        "/manifest.toml" => Response::new(200, "name = \"kvapi\"\nversion = 2\n"),
+        "/status.json" => Response::json(200, json!({ "data": { "name": "kvapi", "version": 3 } })),
+        _ => Response::new(404, ""),
+    })
+    .await;
+    let host = server.host();
+    let api = Formats::new();
+
+    let rates = api.rates.get(&host).await.unwrap();
+    assert_eq!(
+        rates,
+        vec![
+            Rate {
+                date: "2024-01-01".to_string(),
+                value: Some(1.5),
+            },
+            Rate {
+                date: "2024-01-02".to_string(),
+                value: None,
+            },
+        ]
+    );
+
+    let feed = api.feed.get(&host).await.unwrap();
+    assert_eq!(
+        feed,
+        Feed {
+            title: "News".to_string(),
+            entry: vec!["one".to_string(), "two".to_string()],
+        }
+    );
+
+    let config = |name: &str, version| Config {
+        name: name.to_string(),
+        version,
+    };
+    assert_eq!(api.config.get(&host).await.unwrap(), config("kvapi", 1));
+    assert_eq!(api.manifest.get(&host).await.unwrap(), config("kvapi", 2));
+    assert_eq!(api.status.get(&host).await.unwrap(), config("kvapi", 3));
+
+    // decode errors point at the row & column
+    let error = api
+        .rates
+        .request(&host)
+        .case("fail")
+        .get()
+        .await
+        .unwrap_err();
+    let kvapi::Error::Decode { endpoint, path, .. } = &error else {
+        panic!("expected a decode error, found {error:?}");
+    };
+    assert_eq!(*endpoint, "http://{host}/rates.csv");
+    assert_eq!(path, "[1].value");
+
+    // and at a row with fewer, or more, fields than the header
+    for (case, row) in [("short", "[1]"), ("long", "[0]")] {
+        let error = api.rates.request(&host).case(case).get().await.unwrap_err();
+        let kvapi::Error::Decode { path, .. } = &error else {
+            panic!("expected a decode error, found {error:?}");
+        };
+        assert_eq!(path, row);
+        assert!(error.to_string().contains("expected 2 fields, one per header"));
+    }
+}
+
+// `get_bytes()`, `get_text()`, `get_as::<U>()` & `get_response()`
+#[tokio::test]
+async fn raw_access() {
+    let server = Server::start(|request| match request.target.as_str() {
+        "/schema" => Response::json(200, json!([{ "date": "2024-01-01", "value": "." }])),
+        "/status/404" => Response::new(404, "not found"),
+        _ => Response::new(200, format!("key={}", request.header("X-Api-Key").unwrap())),
+    })
+    .await;
+    let host = server.host();
+
+    // the API's headers are still sent
+    let api = Keyed::new();
+    assert_eq!(api.ping.get_text(&host).await.unwrap(), "key=abc");
+    assert_eq!(&api.pong.get_bytes(&host).await.unwrap()[..], b"key=abc");
+
+    // another type than the declared `Vec<Observation>`
+    let api = Failing::new();
+    assert!(api.schema.get(&host).await.unwrap_err().is_decode());
+    let schema: Value = api.schema.get_as(&host).await.unwrap();
+    assert_eq!(schema[0]["value"], ".");
+
+    // the response as is, whatever its status
+    let response = api.status.get_response(&host, 404).await.unwrap();
+    assert_eq!(response.status(), kvapi::StatusCode::NOT_FOUND);
+    assert_eq!(response.text().await.unwrap(), "not found");
+    let error = api.status.get_text(&host, 404).await.unwrap_err();
+    assert_eq!(error.status(), Some(kvapi::StatusCode::NOT_FOUND));
+}
+
+// `get_with_meta()`
+#[tokio::test]
+async fn response_metadata() {
+    let server = Server::start(|request| match request.target.as_str() {
+        "/ping" => Response::new(302, "").header("Location", "/pong"),
+        _ => Response::json(200, json!({ "pong": true }))
+            .header("X-MBX-USED-WEIGHT", "2")
+            .header("ETag", "\"v1\""),
+    })
+    .await;
+    let (host, api) = (server.host(), Keyed::new());
+
+    let before = std::time::SystemTime::now();
+    let response = api.ping.get_with_meta(&host).await.unwrap();
+    assert_eq!(response.status, kvapi::StatusCode::OK);
+    assert_eq!(response.header("x-mbx-used-weight"), Some("2"));
+    assert_eq!(response.header("etag"), Some("\"v1\""));
+    assert_eq!(response.header("retry-after"), None);
+    assert_eq!(response.url.path(), "/pong"); // redirected
+    assert!(response.sent_at >= before && response.elapsed > std::time::Duration::ZERO);
+
+    // the data, through `Deref` or `into_inner()`
+    assert_eq!(response["pong"], true);
+    assert_eq!(response.into_inner(), json!({ "pong": true }));
+}
+
+// `middleware: [..]`, and `with_middleware()`
+#[tokio::test]
+async fn middleware() {
+    let server = Server::start(|request| {
+        let trace = request.header("X-Trace").unwrap_or_default();
+        Response::json(
+            200,
+            json!({ "method": request.method, "trace": trace, "body": request.body() }),
+        )
+        .header(
+            "X-Id",
+            request.header("X-Correlation-Id").unwrap_or_default(),
+        )
+    })
+    .await;
+    let api = Intercepted::new().with_middleware(Rewrite(server.host()));
+
+    let response = api.echo.get_with_meta().await.unwrap();
+    assert_eq!(response["method"], "GET");
+    assert_eq!(response["trace"], "on");
+    assert_eq!(response.header("X-Id"), Some("42"));
+    // `after` runs in reverse
+    assert_eq!(response.header("X-Seen"), Some("X-Trace,X-Correlation-Id"));
+
+    let echo = api.echo.post(&json!({ "side": "buy" })).await.unwrap();
+    assert_eq!(echo["method"], "PUT");
+    assert_eq!(echo["body"], r#"{"SIDE":"BUY"}"#);
+    assert_eq!(server.requests().len(), 2);
+
+    // a failing middleware fails the request, before it's sent
+    let error = api.with_middleware(Deny).echo.get().await.unwrap_err();
+    assert!(matches!(error, kvapi::Error::Middleware(_)));
+    assert_eq!(error.to_string(), "middleware failed: denied");
+    assert_eq!(server.requests().len(), 2);
+}
+
+// the built-in signers, against the documented test vectors
+#[tokio::test]
+async fn signers() {
+    use kvapi::signer::{Binance, Kraken, KuCoin, Payload, Signer};
+
+    let header = |signed: &kvapi::signer::Signed, name: &str| {
+        let (_, value) = signed.headers.iter().find(|(key, _)| key == name).unwrap();
+        value.clone()
+    };
+
+    // https://developers.binance.com/docs/binance-spot-api-docs/rest-api/request-security
+    let binance = Binance::new(
+        "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A",
+        "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j",
+    );
+    let payload = Payload {
+        method: "POST",
+        path: "/api/v3/order",
+        query:
+            "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000",
+        body: b"",
+        timestamp: 1499827319559,
+    };
+    let signed = binance.sign(&payload).unwrap();
+    assert_eq!(
+        signed.query,
+        vec![
+            ("timestamp".to_string(), "1499827319559".to_string()),
+            (
+                "signature".to_string(),
+                "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        header(&signed, "X-MBX-APIKEY"),
+        "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A"
+    );
+
+    // https://docs.kraken.com/api/docs/guides/spot-rest-auth
+    let kraken = Kraken::new(
+        "api-key",
+        "kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==",
+    );
+    let payload = Payload {
+        method: "POST",
+        path: "/0/private/AddOrder",
+        query: "",
+        body: b"ordertype=limit&pair=XBTUSD&price=37500&type=buy&volume=1.25",
+        timestamp: 1616492376594,
+    };
+    let signed = kraken.sign(&payload).unwrap();
+    assert_eq!(
+        header(&signed, "API-Sign"),
+        "4/dpxb3iT4tp/ZCVEwSnEsLxx0bqyhLpdfOpc6fn7OR8+UClSV5n9E6aSS8MPtnRfp32bAb0nmbRn6H8ndwLUQ=="
+    );
+    assert_eq!(
+        signed.body.as_deref(),
+        Some(
+            &b"nonce=1616492376594&ordertype=limit&pair=XBTUSD&price=37500&type=buy&volume=1.25"[..]
+        )
+    );
+    let public = Payload {
+        path: "/0/public/Time",
+        ..payload
+    };
+    assert_eq!(kraken.sign(&public).unwrap(), Default::default());
+
+    // a nonce above the last, even within the same millisecond; shared by clones
+    let signed = kraken.clone().sign(&payload).unwrap();
+    assert!(signed.body.unwrap().starts_with(b"nonce=1616492376595&"));
+    let earlier = Payload {
+        timestamp: 1616492376000,
+        ..payload
+    };
+    let signed = kraken.sign(&earlier).unwrap();
+    assert!(signed.body.unwrap().starts_with(b"nonce=1616492376596&"));
+
+    // KuCoin publishes no vectors; these are from `openssl dgst -sha256 -hmac secret -binary | base64`
+    let kucoin = KuCoin::new("api-key", "secret", "passphrase");
+    let payload = Payload {
+        method: "GET",
+        path: "/api/v1/deposit-addresses",
+        query: "currency=BTC",
+        body: b"",
+        timestamp: 1547015186532,
+    };
+    let signed = kucoin.sign(&payload).unwrap();
+    assert_eq!(
+        header(&signed, "KC-API-SIGN"),
+        "KdJKBgwzKz0giF6W7EkyFrm31nAzOmAaX0jqtkgo3Wc="
+    );
+    assert_eq!(
+        header(&signed, "KC-API-PASSPHRASE"),
+        "sWd5rQWAxDzYJTY6K2sov6seA0l3uNP70anWxITg8IA="
+    );
+    assert_eq!(header(&signed, "KC-API-TIMESTAMP"), "1547015186532");
+    let payload = Payload {
+        method: "POST",
+        path: "/api/v1/orders",
+        query: "",
+        body: br#"{"side":"buy","symbol":"BTC-USDT","size":"0.001"}"#,
+        ..payload
+    };
+    let signed = kucoin.sign(&payload).unwrap();
+    assert_eq!(
+        header(&signed, "KC-API-SIGN"),
+        "WmlqOgIqUHKOHUolYzqaMcfJzNDlsmwjE6+5Thtpt5U="
+    );
+
+    // as the middleware of an API; the signature covers the query as sent, even by a
+    // middleware added after the signer
+    let server = Server::start(|_| Response::json(200, json!({}))).await;
+    let host = server.host();
+    for (api, sent) in [
+        (Signed::new(), "symbol=BNBBTC&timestamp="),
+        (Signed::new().with_middleware(Tag), "symbol=BNBBTC&tag=1&timestamp="),
+    ] {
+        api.order.request(&host).symbol("BNBBTC").get().await.unwrap();
+
+        let request = server.requests().pop().unwrap();
+        assert_eq!(request.header("X-MBX-APIKEY"), Some("api-key"));
+        let (query, signature) = request.target.split_once("&signature=").unwrap();
+        let (_, query) = query.split_once('?').unwrap();
+        assert!(query.starts_with(sent), "{query}");
+        let expected = kvapi::signer::hmac_sha256(b"secret", query.as_bytes());
+        let expected: String = expected.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(signature, expected);
+    }
+}
+
+// `auth: Bearer(..)`, `Basic(..)` & `ApiKey { .. }`
+#[tokio::test]
+async fn auth() {
+    let server = Server::start(|request| match request.target.as_str() {
+        "/series?file_type=json&series_id=GONE&api_key=k3y" => Response::new(404, "gone"),
+        // cut short
+        "/truncated?api_key=k3y" => Response::new(200, "{").header("Content-Length", "100"),
+        _ => Response::json(
+            200,
+            json!({ "authorization": request.header("Authorization") }),
+        ),
+    })
+    .await;
+    let host = server.host();
+
+    let me = Tokened::new().me.get(&host).await.unwrap();
+    assert_eq!(me["authorization"], "Bearer t0ken");
+    let me = Logged::new().me.get(&host).await.unwrap();
+    assert_eq!(me["authorization"], "Basic dXNlcjpwYXNz"); // `user:pass`
+
+    // in the query, when sent; redacted from the URL of responses & errors
+    std::env::set_var("KVAPI_TEST_API_KEY", "k3y");
+    let api = QueryKeyed::new();
+    let series = api.series.request(&host).series_id("DGS10");
+    let response = series.get_with_meta().await.unwrap();
+    assert_eq!(
+        response.url.query(),
+        Some("file_type=json&series_id=DGS10&api_key=***")
+    );
+    let request = server.requests().pop().unwrap();
+    assert_eq!(
+        request.target,
+        "/series?file_type=json&series_id=DGS10&api_key=k3y"
+    );
+
+    let error = api
+        .series
+        .request(&host)
+        .series_id("GONE")
+        .get()
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), Some(kvapi::StatusCode::NOT_FOUND));
+    assert!(error.to_string().contains("api_key=***") && !error.to_string().contains("k3y"));
+    let response = series.get_response().await.unwrap();
+    assert_eq!(
+        response.url().query(),
+        Some("file_type=json&series_id=DGS10&api_key=***")
+    );
+    assert_eq!(response.text().await.unwrap(), r#"{"authorization":null}"#);
+
+    // even if a cached response can't be read
+    let api = kvapi::Api::new(kvapi::Client::new(), Default::default())
+        .with_auth(kvapi::Auth::query("api_key", "k3y"))
+        .with_cache(kvapi::Cache::default().ttl(std::time::Duration::from_secs(60)))
+        .with_timeout(std::time::Duration::from_millis(100));
+    let url = format!("http://{host}/truncated");
+    let error = api.bytes(api.client().get(&url)).await.unwrap_err();
+    assert!(!error.to_string().contains("k3y"), "{error}");
+
+    // the secret is kept out of `Debug`
+    let auth = kvapi::Auth::query("api_key", "k3y");
+    assert!(!format!("{auth:?}").contains("k3y"));
+
+    // read when the API is built
+    let error = Anonymous::try_new().err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "failed to build `Anonymous`: invalid auth: `KVAPI_TEST_UNSET_TOKEN`: environment variable not found"
+    );
+}
+
+// `auth: OAuth2 { .. }`, against a local token endpoint
+#[tokio::test]
+async fn oauth2() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let issued = AtomicUsize::new(0);
+    let server = Server::start(move |request| match request.target.as_str() {
+        // `t1`, `t2`, ..; the first refresh-token grant expires within the renewal margin
+        "/token" => {
+            let n = issued.fetch_add(1, Ordering::SeqCst) + 1;
+            let expires_in = match request.body().contains("refresh_token=r0") {
+                true => 10,
+                false => 3600,
+            };
+            let token = json!({
+                "access_token": format!("t{n}"),
+                "token_type": "Bearer",
+                "expires_in": expires_in,
+                "refresh_token": format!("r{n}"),
+            });
+            Response::json(200, token)
+        }
+        "/strict" if request.header("Authorization") == Some("Bearer t1") => {
+            Response::new(401, "expired")
+        }
+        _ => Response::json(
+            200,
+            json!({ "authorization": request.header("Authorization") }),
+        ),
+    })
+    .await;
+    let host = server.host();
+    std::env::set_var("KVAPI_TEST_TOKEN_URL", format!("{}/token", server.url()));
+    let grants = || {
+        let requests = server.requests().into_iter();
+        let tokens = requests.filter(|request| request.target == "/token");
+        tokens
+            .map(|request| request.body().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // one token, for concurrent requests to any endpoint
+    let api = Delegated::new();
+    let (a, b, c) = tokio::join!(
+        api.data.get(&host),
+        api.data.get(&host),
+        api.strict.get(&host)
+    );
+    assert_eq!(a.unwrap()["authorization"], "Bearer t1");
+    assert_eq!(b.unwrap()["authorization"], "Bearer t1");
+    // renewed after a `401`, with the refresh token
+    assert_eq!(c.unwrap()["authorization"], "Bearer t2");
+    assert_eq!(
+        grants(),
+        vec![
+            "client_id=id&client_secret=s3cret&grant_type=client_credentials&scope=read+write",
+            "client_id=id&client_secret=s3cret&grant_type=refresh_token&refresh_token=r1&scope=read+write",
+        ]
+    );
+    assert_eq!(
+        api.data.get(&host).await.unwrap()["authorization"],
+        "Bearer t2"
+    );
+    assert_eq!(grants().len(), 2);
+
+    // the refresh-token grant; renewed before it expires
+    let api = Refreshed::new();
+    assert_eq!(
+        api.data.get(&host).await.unwrap()["authorization"],
+        "Bearer t3"
+    );
+    assert_eq!(
+        api.data.get(&host).await.unwrap()["authorization"],
+        "Bearer t4"
+    );
+    let grants = grants();
+    assert!(grants[2].ends_with("grant_type=refresh_token&refresh_token=r0"));
+    assert!(grants[3].ends_with("grant_type=refresh_token&refresh_token=r3"));
+}
+
+// `rate_limit: 10/sec`, with `#[weight]`; shared by every endpoint, and across APIs
+#[tokio::test]
+async fn rate_limit() {
+    use std::time::Duration;
+
+    let server = Server::start(|_| Response::json(200, json!({}))).await;
+    let host = server.host();
+
+    let api = Throttled::new();
+    let rate_limit = api.rate_limit().unwrap();
+    assert_eq!(
+        (rate_limit.capacity(), rate_limit.per()),
+        (10, Duration::from_secs(1))
+    );
+
+    // each request takes its weight; of a bucket too slow to refill, meanwhile
+    let rate_limit = kvapi::RateLimit::per_minute(10);
+    let api = Throttled::new().with_rate_limit(rate_limit.clone());
+    api.depth.get(&host).await.unwrap();
+    assert_eq!(rate_limit.available().await, 5);
+
+    // another API takes from the same bucket
+    let neighbour = Neighbour::new().with_rate_limit(rate_limit.clone());
+    neighbour.depth.get(&host).await.unwrap();
+    assert_eq!(rate_limit.available().await, 0);
+
+    // a weight of 1, by default
+    let rate_limit = kvapi::RateLimit::per_minute(10);
+    let api = Throttled::new().with_rate_limit(rate_limit.clone());
+    api.ping.get(&host).await.unwrap();
+    assert_eq!(rate_limit.available().await, 9);
+
+    // without it, there's no limit
+    let neighbour = Neighbour::new();
+    assert!(neighbour.rate_limit().is_none());
+    for _ in 0..5 {
+        neighbour.depth.get(&host).await.unwrap();
+    }
+    assert_eq!(server.requests().len(), 8);
+}
+
+// a full bucket, then `capacity` tokens every `per`; on a paused clock
+#[tokio::test(start_paused = true)]
+async fn rate_limit_waits() {
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    let rate_limit = kvapi::RateLimit::per_second(10);
+    let start = Instant::now();
+    rate_limit.acquire(5).await;
+    rate_limit.acquire(5).await;
+    assert_eq!(start.elapsed(), Duration::ZERO);
+
+    // a token every 100ms
+    rate_limit.acquire(1).await;
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(100) && elapsed < Duration::from_millis(110));
+
+    // a weight over the capacity takes the whole bucket
+    rate_limit.acquire(20).await;
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(1100) && elapsed < Duration::from_millis(1120));
+    assert_eq!(rate_limit.available().await, 0);
+
+    // clones share the bucket
+    tokio::time::advance(Duration::from_millis(500)).await;
+    let clone = rate_limit.clone();
+    clone.acquire(3).await;
+    assert_eq!(rate_limit.available().await, 2);
+}
+
+// `retry:` of the API, or `#[retry(..)]` of an entry
+#[tokio::test]
+async fn retry() {
+    use std::{
+        collections::HashMap,
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    // every endpoint fails twice, by method; but for `/limited` & `/once`
+    let attempts: Mutex<HashMap<String, usize>> = Mutex::default();
+    let server = Server::start(move |request| {
+        let key = format!("{} {}", request.method, request.target);
+        let mut attempts = attempts.lock().unwrap();
+        let n = attempts.entry(key).or_default();
+        *n += 1;
+        match (request.target.as_str(), *n) {
+            ("/limited", 1) => Response::new(429, "slow down").header("Retry-After", "1"),
+            ("/limited", _) => Response::json(200, json!({ "attempt": *n })),
+            ("/once", _) => Response::new(503, "unavailable").header("Retry-After", "7"),
+            ("/busy", _) => Response::new(503, "unavailable").header("Retry-After", "86400"),
+            (_, 1 | 2) => Response::new(503, "unavailable"),
+            _ => Response::json(200, json!({ "attempt": *n })),
+        }
+    })
+    .await;
+    let host = server.host();
+    let api = Retried::new();
+    let sent = |method: &str, target: &str| {
+        let requests = server.requests().into_iter();
+        requests
+            .filter(|request| request.method == method && request.target == target)
+            .count()
+    };
+
+    // by the API's policy
+    let flaky = api.flaky.get(&host).await.unwrap();
+    assert_eq!(flaky["attempt"], 3);
+    assert_eq!(sent("GET", "/flaky"), 3);
+
+    // not a `POST`, unless the entry opts in
+    let error = api.flaky.post(&host, &json!({})).await.unwrap_err();
+    assert_eq!(error.status(), Some(kvapi::StatusCode::SERVICE_UNAVAILABLE));
+    assert_eq!(sent("POST", "/flaky"), 1);
+    let order = api.orders.post(&host, &json!({})).await.unwrap();
+    assert_eq!(order["attempt"], 3);
+
+    // an entry's own policy
+    let error = api.once.get(&host).await.unwrap_err();
+    assert!(error.is_retryable());
+    assert_eq!(error.retry_after(), Some(Duration::from_secs(7)));
+    assert_eq!(sent("GET", "/once"), 1);
+
+    // after the `Retry-After` of the response
+    let start = Instant::now();
+    let limited = api.limited.get(&host).await.unwrap();
+    assert_eq!(limited["attempt"], 2);
+    assert!(start.elapsed() >= Duration::from_millis(900));
+
+    // but not for longer than `max_wait`; it's the caller's to wait, or not
+    let start = Instant::now();
+    let error = api.busy.get(&host).await.unwrap_err();
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(error.retry_after(), Some(Duration::from_secs(86400)));
+    assert_eq!(sent("GET", "/busy"), 1);
+
+    // a failed connection
+    let closed = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }; // dropped; nothing listens
+    let error = api.flaky.get(&closed).await.unwrap_err();
+    assert!(error.is_retryable() && error.status().is_none());
+}
+
+// `timeout:` of the API, `#[timeout]` of an entry, or `with_timeout()` of a request
+#[tokio::test]
+async fn timeouts() {
+    use std::time::Duration;
+
+    // `?delay={ms}`
+    let server = Server::start(|request| {
+        let delay = request.target.split("delay=").nth(1).unwrap_or("100");
+        let delay = Duration::from_millis(delay.parse().unwrap());
+        Response::json(200, json!({})).delay(delay)
+    })
+    .await;
+    let host = server.host();
+    let api = Slow::new();
+
+    // the API's
+    let report = api.report.request(&host).delay(100);
+    report.get().await.unwrap();
+    let error = api
+        .report
+        .request(&host)
+        .delay(500)
+        .get()
+        .await
+        .unwrap_err();
+    assert!(matches!(error, kvapi::Error::Timeout(_)));
+    assert!(error.is_timeout() && error.is_retryable());
+
+    // the entry's, over the API's
+    let error = api
+        .ticker
+        .request(&host)
+        .delay(100)
+        .get()
+        .await
+        .unwrap_err();
+    assert!(error.is_timeout());
+
+    // the request's, over the entry's
+    let ticker = api.ticker.request(&host).delay(100);
+    ticker
+        .with_timeout(Duration::from_secs(1))
+        .get()
+        .await
+        .unwrap();
+
+    // retried, as any timeout
+    let error = api.bulk.get(&host).await.unwrap_err();
+    assert!(error.is_timeout());
+    let bulk = server.requests().into_iter();
+    assert_eq!(bulk.filter(|request| request.target == "/bulk").count(), 2);
+}
+
+// `cache:` of the API, or `#[cache]` of an entry; revalidated by `ETag`, in memory or on disk
+#[tokio::test]
+async fn cache() {
+    use kvapi::cache::CacheStatus;
+    use std::time::Duration;
+
+    // `ETag: "v1"`, unless `/tickers` is asked for twice
+    let server = Server::start(|request| {
+        let etag = request.header("If-None-Match");
+        match etag {
+            Some("\"v1\"") => Response::new(304, ""),
+            _ => Response::json(200, json!({ "target": request.target })).header("ETag", "\"v1\""),
+        }
+    })
+    .await;
+    let host = server.host();
+    let sent = |target: &str| {
+        let requests = server.requests().into_iter();
+        requests.filter(|request| request.target == target).count()
+    };
+    let api = Cached::new();
+
+    // the API's ttl; a hit, then revalidated once stale
+    let quote = api.quote.get_with_meta(&host).await.unwrap();
+    assert_eq!(quote.cache, CacheStatus::Miss);
+    let quote = api.quote.get_with_meta(&host).await.unwrap();
+    assert_eq!(quote.cache, CacheStatus::Hit);
+    assert!(quote.cache.is_hit());
+    assert_eq!(quote.header("etag"), Some("\"v1\""));
+    assert_eq!(quote["target"], "/quote");
+    assert_eq!(sent("/quote"), 1);
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    let quote = api.quote.get_with_meta(&host).await.unwrap();
+    assert_eq!(quote.cache, CacheStatus::Revalidated);
+    assert_eq!(quote["target"], "/quote");
+    let requests = server.requests();
+    assert_eq!(requests[1].header("If-None-Match"), Some("\"v1\""));
+    assert_eq!(
+        api.quote.get_with_meta(&host).await.unwrap().cache,
+        CacheStatus::Hit
+    );
+
+    // not a `POST`
+    api.quote.post(&host, &json!({})).await.unwrap();
+    api.quote.post(&host, &json!({})).await.unwrap();
+    assert_eq!(sent("/quote"), 4);
+
+    // the entry's ttl, over the API's
+    api.tickers.get(&host).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    let tickers = api.tickers.get_with_meta(&host).await.unwrap();
+    assert_eq!(tickers.cache, CacheStatus::Hit);
+    assert_eq!(sent("/tickers"), 1);
+
+    // on disk, across instances; only entries with `#[cache]`, without the API's ttl
+    let dir = std::env::temp_dir().join(format!("kvapi-cache-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    Persisted::new().tickers.get(&host).await.unwrap();
+    let tickers = Persisted::new().tickers.get_with_meta(&host).await.unwrap();
+    assert_eq!(tickers.cache, CacheStatus::Hit);
+    assert_eq!(tickers["target"], "/tickers");
+    assert_eq!(sent("/tickers"), 2);
+    let quote = Persisted::new().quote.get_with_meta(&host).await.unwrap();
+    assert_eq!(quote.cache, CacheStatus::Miss);
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // by the API's credentials; APIs with other keys share a store, not their responses
+    let store = kvapi::Cache::new(kvapi::cache::Memory::new(16)).ttl(Duration::from_secs(3600));
+    let keyed = |key: &str| {
+        kvapi::Api::new(kvapi::Client::new(), Default::default())
+            .with_auth(kvapi::Auth::query("key", key))
+            .with_cache(store.clone())
+    };
+    let url = format!("http://{host}/private");
+    for api in [keyed("alice"), keyed("bob"), keyed("alice")] {
+        let response = api.execute(api.client().get(&url)).await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+    let targets: Vec<String> = server
+        .requests()
+        .into_iter()
+        .map(|request| request.target)
+        .filter(|target| target.starts_with("/private"))
+        .collect();
+    assert_eq!(targets, ["/private?key=alice", "/private?key=bob"]);
+}
+
+// `#[paginate(..)]`; by offset, cursor, or `Link` header
+#[tokio::test]
+async fn pagination() {
+    use kvapi::futures::{StreamExt, TryStreamExt};
+
+    let server = Server::start(|request| {
+        let target = request.target.as_str();
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let param = |key: &str| {
+            let mut pairs = query.split('&').filter_map(|pair| pair.split_once('='));
+            pairs.find(|(k, _)| *k == key).map(|(_, value)| value.to_string())
+        };
+        match path {
+            // 5 series, `limit` at a time
+            "/category/series" => {
+                let offset: usize = param("offset").map_or(0, |n| n.parse().unwrap());
+                let limit: usize = param("limit").unwrap().parse().unwrap();
+                let ids = ["A", "B", "C", "D", "E"].into_iter().skip(offset).take(limit);
+                let seriess: Vec<Value> = ids.map(|id| json!({ "id": id })).collect();
+                Response::json(200, json!({ "count": 5, "seriess": seriess }))
+            }
+            // the same page, whatever the offset
+            "/ignored" => Response::json(200, json!([1, 2])),
+            "/trades" => match param("cursor").as_deref() {
+                None => Response::json(200, json!({ "data": [1, 2], "meta": { "next": "c2" } })),
+                _ => Response::json(200, json!({ "data": [3], "meta": { "next": null } })),
+            },
+            "/history" | "/broken" => match param("page").as_deref() {
+                None => Response::json(200, json!([1, 2]))
+                    .header("Link", &format!("<{path}?page=2>; rel=\"next\", <{path}>; rel=\"first\"")),
+                Some(_) if path == "/broken" => Response::new(503, "unavailable"),
+                Some(_) => Response::json(200, json!([3])),
+            },
+            _ => Response::new(404, ""),
+        }
+    })
+    .await;
+    let host = server.host();
+    let api = Paged::new();
+
+    // by offset, `size` at a time, until the `total`
+    let request = api.series.request(&host).category_id(1);
+    let series: Vec<Series> = request.items().try_collect().await.unwrap();
+    let ids: Vec<&str> = series.iter().map(|series| series.id.as_str()).collect();
+    assert_eq!(ids, ["A", "B", "C", "D", "E"]);
+    let targets: Vec<String> = server.requests().into_iter().map(|r| r.target).collect();
+    assert_eq!(
+        targets,
+        [
+            "/category/series?category_id=1&limit=2",
+            "/category/series?category_id=1&limit=2&offset=2",
+            "/category/series?category_id=1&limit=2&offset=4",
+        ]
+    );
+    let pages = api.series.request(&host).category_id(1).pages();
+    let pages: Vec<CategorySeries> = pages.try_collect().await.unwrap();
+    assert_eq!(pages.len(), 3);
+    assert_eq!((pages[2].count, pages[2].seriess.len()), (5, 1));
+
+    // a missing parameter is the only item
+    let items: Vec<_> = api.series.items(&host).collect().await;
+    assert!(matches!(items[..], [Err(kvapi::Error::Url(_))]));
+
+    // a page that repeats the last one ends it; without a `limit` or `total`, it wouldn't end
+    let ignored: Vec<u32> = api.ignored.items(&host).try_collect().await.unwrap();
+    assert_eq!(ignored, [1, 2]);
+    let requests = server.requests();
+    assert_eq!(requests[requests.len() - 1].target, "/ignored?offset=2");
+
+    // by cursor, until there's no `next`
+    let trades: Vec<Value> = api.trades.items(&host).try_collect().await.unwrap();
+    assert_eq!(trades, [json!(1), json!(2), json!(3)]);
+    let requests = server.requests();
+    assert_eq!(requests[requests.len() - 1].target, "/trades?cursor=c2");
+
+    // by the `Link` header, until there's no `rel="next"`
+    let history: Vec<u32> = api.history.items(&host).try_collect().await.unwrap();
+    assert_eq!(history, [1, 2, 3]);
+    let pages: Vec<Vec<u32>> = api.history.pages(&host).try_collect().await.unwrap();
+    assert_eq!(pages, [vec![1, 2], vec![3]]);
+
+    // a failed page ends the stream
+    let broken: Vec<_> = api.broken.items(&host).collect().await;
+    assert_eq!(broken.len(), 3);
+    assert!(broken[..2].iter().all(Result::is_ok));
+    assert_eq!(
+        broken[2].as_ref().unwrap_err().status(),
+        Some(kvapi::StatusCode::SERVICE_UNAVAILABLE)
+    );
+}
+
+// `watch(interval)`; every response, or only changes, skipping errors, until cancelled
+#[tokio::test]
+async fn watch() {
+    use kvapi::{futures::StreamExt, retry::Backoff};
+    use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+    // by feed: the same price twice, a failure, the same price in another body, then a new one
+    let polls: Mutex<HashMap<String, usize>> = Mutex::default();
+    let server = Server::start(move |request| {
+        let mut polls = polls.lock().unwrap();
+        let n = polls.entry(request.target.clone()).or_default();
+        *n += 1;
+        match *n {
+            1 | 2 => Response::json(200, json!({ "price": 1 })),
+            3 => Response::new(503, "unavailable"),
+            4 => Response::json(200, json!({ "price": 1, "time": 4 })),
+            _ => Response::json(200, json!({ "price": 2 })),
+        }
+    })
+    .await;
+    let host = server.host();
+    let api = Watched::new();
+    let interval = Duration::from_millis(20);
+
+    // every response, and every error
+    let all: Vec<_> = api.ticker.watch(&host, "all", interval).take(5).collect().await;
+    let prices: Vec<Option<u32>> = all.iter().map(|t| Some(t.as_ref().ok()?.price)).collect();
+    assert_eq!(prices, [Some(1), Some(1), None, Some(1), Some(2)]);
+    assert!(all[2].as_ref().unwrap_err().is_retryable());
+
+    // only a new body
+    let backoff = Backoff::constant(Duration::from_millis(10));
+    let watch = api.ticker.watch(&host, "body", interval).distinct();
+    let body: Vec<_> = watch.skip_errors(backoff).take(3).collect().await;
+    let prices: Vec<u32> = body.into_iter().map(|t| t.unwrap().price).collect();
+    assert_eq!(prices, [1, 1, 2]);
+
+    // only a new value
+    let watch = api.ticker.request(&host, "eq").watch(interval).distinct_eq();
+    let eq: Vec<_> = watch.skip_errors(backoff).take(2).collect().await;
+    let prices: Vec<u32> = eq.into_iter().map(|t| t.unwrap().price).collect();
+    assert_eq!(prices, [1, 2]);
+    let sent = server.requests().into_iter();
+    assert_eq!(sent.filter(|r| r.target == "/ticker/eq").count(), 5);
+
+    // until cancelled; even before the first request
+    let token = kvapi::CancellationToken::new();
+    let watch = api.ticker.watch(&host, "cancel", interval).until(token.clone());
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        cancel.cancel();
+    });
+    let timeout = Duration::from_secs(2);
+    let cancelled: Vec<_> = tokio::time::timeout(timeout, watch.collect()).await.unwrap();
+    assert!(!cancelled.is_empty());
+    let watch = api.ticker.watch(&host, "never", interval).until(token);
+    assert!(watch.collect::<Vec<_>>().await.is_empty());
+    assert!(server.requests().iter().all(|r| r.target != "/ticker/never"));
+}
+
+// `get_many(items)`; by the path & required query parameters, a few at a time
+#[tokio::test]
+async fn get_many() {
+    use kvapi::futures::StreamExt;
+    use std::time::{Duration, Instant};
+
+    // `?symbol=`, echoed; but for `BAD`, and `SLOW`, or `WAIT`
+    let server = Server::start(|request| {
+        let symbol = request.target.split("symbol=").nth(1).unwrap_or_default();
+        let symbol = symbol.split('&').next().unwrap().to_string();
+        let response = Response::json(200, json!({ "symbol": symbol, "target": request.target }));
+        match symbol.as_str() {
+            "BAD" => Response::new(404, "unknown symbol"),
+            "SLOW" => response.delay(Duration::from_millis(300)),
+            "WAIT" => response.delay(Duration::from_millis(100)),
+            _ => response,
+        }
+    })
+    .await;
+    let host = server.host();
+    let api = Batched::new();
+
+    // in order; a failure is only its own
+    let items = [(host.as_str(), "A"), (&host, "B"), (&host, "BAD"), (&host, "C")];
+    let prices = api.price.get_many(items).await;
+    assert_eq!(prices.len(), 4);
+    assert_eq!(prices[0].as_ref().unwrap()["symbol"], "A");
+    let error = prices[2].as_ref().unwrap_err();
+    assert_eq!(error.status(), Some(kvapi::StatusCode::NOT_FOUND));
+    assert_eq!(prices[3].as_ref().unwrap()["symbol"], "C");
+
+    // the request's other parameters, for every item
+    let request = api.price.request(&host).window(5);
+    let prices = request.get_many(["A", "B"]).await;
+    assert_eq!(prices[1].as_ref().unwrap()["target"], "/price?window=5&symbol=B");
+    let series = api.series.get_many([(host.as_str(), 1, "GDP"), (&host, 2, "CPI")]).await;
+    assert_eq!(
+        series[1].as_ref().unwrap()["target"],
+        "/category/2/series?series_id=CPI"
+    );
+
+    // as each is done
+    let request = api.price.request(&host);
+    let done: Vec<_> = request.get_many(["SLOW", "A"]).unordered().collect().await;
+    let order: Vec<usize> = done.iter().map(|(i, _)| *i).collect();
+    assert_eq!(order, [1, 0]);
+
+    // `concurrency` at a time; of a full bucket
+    let (start, api) = (Instant::now(), Batched::new());
+    let request = api.price.request(&host);
+    let prices = request.get_many(["WAIT"; 6]).concurrency(3).stream();
+    assert_eq!(prices.filter(|price| std::future::ready(price.is_ok())).count().await, 6);
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(200) && elapsed < Duration::from_millis(400));
+
+    // within the rate limit, of 10 a second
+    let (start, api) = (Instant::now(), Batched::new());
+    let prices = api.price.request(&host).get_many(["A"; 15]).concurrency(15).await;
+    assert!(prices.iter().all(Result::is_ok));
+    assert!(start.elapsed() >= Duration::from_millis(400));
+}
+
+// `ws:`; subscribed on each connection, reconnected once it drops, or goes quiet, until cancelled
+#[tokio::test]
+async fn subscribe() {
+    use common::WsServer;
+    use kvapi::futures::StreamExt;
+    use std::time::Duration;
+
+    // an acknowledgement & two tickers, then a dropped connection; then another ticker
+    let server = WsServer::start(|n, mut connection| async move {
+        connection.recv().await;
+        match n {
+            0 => {
+                connection.send(json!({ "result": null })).await;
+                connection.send(json!({ "price": 1 })).await;
+                connection.send(json!({ "price": 2 })).await;
+            }
+            _ => {
+                connection.send(json!({ "price": 3 })).await;
+                connection.drain().await;
+            }
+        }
+    })
+    .await;
+    let api = Streamed::new().with_ws_url(format!("{}/ws", server.url()));
+    let token = kvapi::CancellationToken::new();
+    let mut tickers = api.ws.ticker.subscribe("btcusdt").until(token.clone());
+
+    let mut received = vec![];
+    for _ in 0..4 {
+        received.push(tickers.next().await.unwrap());
+    }
+    let prices: Vec<Option<u32>> = received.iter().map(|t| Some(t.as_ref().ok()?.price)).collect();
+    assert_eq!(prices, [Some(1), Some(2), None, Some(3)]);
+    assert!(matches!(received[2], Err(kvapi::Error::WebSocket(_))));
+
+    // unsubscribed, once cancelled
+    token.cancel();
+    assert!(tickers.next().await.is_none());
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let subscribe = r#"{"method": "SUBSCRIBE", "params": ["btcusdt@ticker"]}"#;
+    let unsubscribe = r#"{"method": "UNSUBSCRIBE", "params": ["btcusdt@ticker"]}"#;
+    assert_eq!(server.messages(), [subscribe, subscribe, unsubscribe]);
+    assert_eq!(server.paths(), ["/ws", "/ws"]);
+
+    // an unanswered ping drops the connection
+    let server = WsServer::start(|n, mut connection| async move {
+        match n {
+            0 => connection.hold(Duration::from_secs(1)).await,
+            _ => {
+                connection.recv().await;
+                connection.send(json!([{ "price": 4 }])).await;
+                connection.drain().await;
+            }
+        }
+    })
+    .await;
+    let api = Streamed::new().with_ws_url(server.url());
+    let mut tickers = api.ws.ticker_arr.subscribe();
+    let error = tickers.next().await.unwrap().unwrap_err();
+    assert!(error.to_string().contains("ping"), "{error}");
+    assert_eq!(tickers.next().await.unwrap().unwrap(), [Ticker { price: 4 }]);
+    let quiet = tokio::time::timeout(Duration::from_millis(150), tickers.next()).await;
+    assert!(quiet.is_err());
+    assert!(server.pings() > 0);
+
+    // without a `subscribe` message, by the stream's URL; pinged, and answered, by text
+    let server = WsServer::start(|_, mut connection| async move {
+        connection.send(json!({ "result": null })).await;
+        connection.send(json!({ "price": 5 })).await;
+        while let Some(message) = connection.recv().await {
+            if message == "ping" {
+                connection.send(json!("pong")).await;
+            }
+        }
+    })
+    .await;
+    let api = Raw::new().with_ws_url(format!("{}/ws/", server.url()));
+    let mut tickers = api.ws.ticker.subscribe("ethusdt");
+    assert_eq!(tickers.next().await.unwrap().unwrap().price, 5);
+    let quiet = tokio::time::timeout(Duration::from_millis(100), tickers.next()).await;
+    assert!(quiet.is_err());
+    assert!(server.messages().iter().any(|message| message == "ping"));
+    assert_eq!(server.paths(), ["/ws/ethusdt@ticker"]);
+
+    // only a message of the stream's type, unless it's strict
+    let mut tickers = api.ws.ticker.subscribe("ethusdt").strict();
+    assert!(tickers.next().await.unwrap().unwrap_err().is_decode());
+    assert_eq!(tickers.next().await.unwrap().unwrap().price, 5);
+
+    // a failed connection is yielded, then tried again
+    let api = Raw::new().with_ws_url("ws://127.0.0.1:9");
+    let mut tickers = api.ws.ticker.subscribe("ethusdt");
+    assert!(matches!(tickers.next().await, Some(Err(kvapi::Error::WebSocket(_)))));
+    assert!(matches!(tickers.next().await, Some(Err(kvapi::Error::WebSocket(_)))));
+}
@@ -0,0 +1,305 @@
+// A minimal HTTP/1.1 server, for testing generated clients without the network.
+//
+// Each request is recorded, and answered by the handler given to `Server::start`. `WsServer` is
+// the same, for WebSocket connections.
+#![allow(dead_code)]
+
+use futures::{SinkExt, StreamExt};
+use std::{
+    future::Future,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tokio_tungstenite::{
+    tungstenite::{
+        handshake::server::{Request as Handshake, Response as Accept},
+        Message,
+    },
+    WebSocketStream,
+};
+
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    pub target: String, // path & query, e.g., `/orders/1?limit=10`
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn body(&self) -> &str {
+        std::str::from_utf8(&self.body).expect("utf-8 body")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub delay: Duration, // before it's sent
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: body.into(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn json(status: u16, json: serde_json::Value) -> Self {
+        Self::new(status, json.to_string()).header("Content-Type", "application/json")
+    }
+
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        self.headers.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Answer after `delay`, i.e., to time out the client.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+pub struct Server {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl Server {
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("local addr");
+        let requests: Arc<Mutex<Vec<Request>>> = Arc::default();
+        let handler = Arc::new(handler);
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (handler, recorded) = (handler.clone(), recorded.clone());
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut reader = BufReader::new(reader);
+
+                    // keep-alive: answer requests until the client hangs up
+                    while let Some(request) = read_request(&mut reader).await {
+                        let mut response = handler(&request);
+                        if request.method == "HEAD" {
+                            let length = response.body.len().to_string();
+                            response = response.header("Content-Length", &length);
+                            response.body.clear();
+                        }
+                        recorded.lock().unwrap().push(request);
+                        tokio::time::sleep(response.delay).await;
+                        if writer.write_all(&encode(response)).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        Self { addr, requests }
+    }
+
+    /// `127.0.0.1:{port}`
+    pub fn host(&self) -> String {
+        self.addr.to_string()
+    }
+
+    /// `http://127.0.0.1:{port}`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request<R>(reader: &mut BufReader<R>) -> Option<Request>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut line = String::new();
+    reader.read_line(&mut line).await.ok()?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = (parts.next()?.to_string(), parts.next()?.to_string());
+
+    let mut headers = vec![];
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (key, value) = line.split_once(':')?;
+        headers.push((key.trim().to_string(), value.trim().to_string()));
+    }
+
+    let length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await.ok()?;
+
+    Some(Request {
+        method,
+        target,
+        headers,
+        body,
+    })
+}
+
+fn encode(response: Response) -> Vec<u8> {
+    let mut head = format!("HTTP/1.1 {} Mock\r\n", response.status);
+    for (key, value) in response.headers.iter() {
+        head.push_str(&format!("{key}: {value}\r\n"));
+    }
+    if !response
+        .headers
+        .iter()
+        .any(|(key, _)| key == "Content-Length")
+    {
+        head.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+    }
+    head.push_str("\r\n");
+
+    let mut bytes = head.into_bytes();
+    bytes.extend(response.body);
+    bytes
+}
+
+pub struct WsServer {
+    addr: SocketAddr,
+    paths: Arc<Mutex<Vec<String>>>,    // of each connection, in order
+    messages: Arc<Mutex<Vec<String>>>, // the text of each, from every connection
+    pings: Arc<Mutex<usize>>,          // Ping frames
+}
+
+/// A connection to `WsServer`; dropped without a close frame, it's as if the network failed.
+pub struct WsConnection {
+    socket: WebSocketStream<TcpStream>,
+    messages: Arc<Mutex<Vec<String>>>,
+    pings: Arc<Mutex<usize>>,
+}
+
+impl WsServer {
+    /// Each connection is handed to `handler`, by its index, once it's accepted.
+    pub async fn start<F, Fut>(handler: F) -> Self
+    where
+        F: Fn(usize, WsConnection) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("local addr");
+        let server = Self {
+            addr,
+            paths: Arc::default(),
+            messages: Arc::default(),
+            pings: Arc::default(),
+        };
+
+        let (paths, messages, pings) = (
+            server.paths.clone(),
+            server.messages.clone(),
+            server.pings.clone(),
+        );
+        tokio::spawn(async move {
+            let mut n = 0;
+            while let Ok((stream, _)) = listener.accept().await {
+                let paths = paths.clone();
+                // the error is tungstenite's own type; it can't be made smaller
+                #[allow(clippy::result_large_err)]
+                let record = |request: &Handshake, response: Accept| {
+                    paths.lock().unwrap().push(request.uri().to_string());
+                    Ok(response)
+                };
+                let Ok(socket) = tokio_tungstenite::accept_hdr_async(stream, record).await else {
+                    continue;
+                };
+                let connection = WsConnection {
+                    socket,
+                    messages: messages.clone(),
+                    pings: pings.clone(),
+                };
+                tokio::spawn(handler(n, connection));
+                n += 1;
+            }
+        });
+        server
+    }
+
+    /// `ws://127.0.0.1:{port}`
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        self.paths.lock().unwrap().clone()
+    }
+
+    pub fn messages(&self) -> Vec<String> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    pub fn pings(&self) -> usize {
+        *self.pings.lock().unwrap()
+    }
+}
+
+impl WsConnection {
+    pub async fn send(&mut self, json: serde_json::Value) {
+        let message = Message::Text(json.to_string());
+        self.socket.send(message).await.expect("send");
+    }
+
+    /// The next text message, recorded; none once the client hangs up. Pings are answered.
+    pub async fn recv(&mut self) -> Option<String> {
+        while let Some(Ok(message)) = self.socket.next().await {
+            match message {
+                Message::Text(text) => {
+                    self.messages.lock().unwrap().push(text.clone());
+                    return Some(text);
+                }
+                Message::Ping(_) => *self.pings.lock().unwrap() += 1,
+                Message::Close(_) => return None,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Read, and record, every message until the client hangs up.
+    pub async fn drain(mut self) {
+        while self.recv().await.is_some() {}
+    }
+
+    /// Hold the connection open for `duration`, without reading; so pings go unanswered.
+    pub async fn hold(self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
@@ -81,6 +81,24 @@ fn parse_dict() {
         }
     };
     syn::parse2::<Dict>(input).expect("parse Dict [with rename & query attrs]");
+
+    // 5. keys of the same name, but for placeholders; unless one is renamed
+    let input = quote! {
+        {
+            "orders": Vec<Value>,
+            "orders/{id: u32}": Value,
+        }
+    };
+    let error = syn::parse2::<Dict>(input).expect_err("parse Dict [with a leaf twice]");
+    assert!(error.to_string().contains("`rename` one of them"));
+    let input = quote! {
+        {
+            "orders": Vec<Value>,
+            #[rename: "order"]
+            "orders/{id: u32}": Value,
+        }
+    };
+    syn::parse2::<Dict>(input).expect("parse Dict [with a renamed leaf]");
 }
 
 #[test]
@@ -162,6 +180,16 @@ fn parse_dict_entry() {
         Some(quote!( #parsed_query ).to_string()),
         Some("\"?add_this_on_the_end\"".to_string())
     );
+
+    // 5. a body, only with a method to send it
+    let input = quote! { #[method: PUT, body: form] "orders": CreateOrder => OrderAck };
+    assert!(syn::parse2::<Entry>(input).is_ok());
+    let input = quote! { "orders": CreateOrder => OrderAck };
+    assert!(syn::parse2::<Entry>(input).is_ok());
+    let input = quote! { #[method: DELETE] "orders": CreateOrder => OrderAck };
+    assert!(syn::parse2::<Entry>(input).is_err());
+    let input = quote! { #[method: GET, body: bytes] "upload": Value };
+    assert!(syn::parse2::<Entry>(input).is_err());
 }
 
 #[test]
@@ -210,9 +238,9 @@ fn parse_headers() {
         }
     };
     let parsed = syn::parse2::<Headers>(input).expect("parse Headers");
-    let inner = parsed.inner;
-    let requoted = quote!( #( #inner  )*).to_string();
-    assert_eq!(requoted, "let value = \"example@example_domain.com\" ; headers . insert (\"User-Agent\" , reqwest :: header :: HeaderValue :: from_str (value) . unwrap ()) ; let value = & std :: env :: var (\"USER_AGENT\") ; headers . insert (\"Useragent\" , reqwest :: header :: HeaderValue :: from_str (value) . unwrap ()) ;");
+    let client = parsed.client;
+    let requoted = quote!( #( #client  )*).to_string();
+    assert_eq!(requoted, "let value = (|| -> std :: result :: Result < kvapi :: HeaderValue , Box < dyn std :: error :: Error + Send + Sync >> { Ok (kvapi :: HeaderValue :: from_str (\"example@example_domain.com\") ?) }) () . map_err (| source | kvapi :: Error :: Header { api , name : \"User-Agent\" , source }) ? ; headers . insert (\"User-Agent\" , value) ; let value = (|| -> std :: result :: Result < kvapi :: HeaderValue , Box < dyn std :: error :: Error + Send + Sync >> { Ok (kvapi :: HeaderValue :: from_str (& std :: env :: var (\"USER_AGENT\")) ?) }) () . map_err (| source | kvapi :: Error :: Header { api , name : \"Useragent\" , source }) ? ; headers . insert (\"Useragent\" , value) ;");
 }
 
 #[test]
@@ -237,4 +265,630 @@ fn parse_header() {
         parsed_val,
         "& std :: env :: var (\"USER_AGENT\")".to_string()
     );
+
+    // 3. invalid names are rejected
+    let input = quote! { "User Agent": "example@domain.com" };
+    assert!(syn::parse2::<Header>(input).is_err());
+    let input = quote! { "": "example@domain.com" };
+    assert!(syn::parse2::<Header>(input).is_err());
+}
+
+// api/path.rs
+// ===========
+//
+// Template, PathParam
+#[test]
+fn parse_path_template() {
+    use kvapi_macros_internals::api::path::Template;
+
+    // 1. no placeholders
+    let input: syn::LitStr = syn::parse_quote!("/this/is/an/endpoint.json");
+    let parsed = Template::parse(&input).expect("parse Template; no placeholders");
+    assert_eq!(parsed.format, "/this/is/an/endpoint.json");
+    assert_eq!(parsed.name, "/this/is/an/endpoint.json");
+    assert!(parsed.params.is_empty());
+
+    // 2. untyped & typed placeholders
+    let input: syn::LitStr = syn::parse_quote!("series/{series_id}/observations/{id: u32}");
+    let parsed = Template::parse(&input).expect("parse Template; with placeholders");
+    assert_eq!(parsed.format, "series/{}/observations/{}");
+    assert_eq!(parsed.name, "series//observations/");
+    let params = parsed
+        .params
+        .iter()
+        .map(|param| {
+            let (name, ty) = (&param.name, &param.ty);
+            quote!( #name: #ty ).to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(params, vec!["series_id : & str", "id : u32"]);
+
+    // 3. escaped braces
+    let input: syn::LitStr = syn::parse_quote!("literal/{{braces}}/{id}");
+    let parsed = Template::parse(&input).expect("parse Template; escaped braces");
+    assert_eq!(parsed.format, "literal/{{braces}}/{}");
+    assert_eq!(parsed.params.len(), 1);
+
+    // 4. placeholders after the `?` are query values
+    let input: syn::LitStr = syn::parse_quote!("markets/{market}/ticker?symbol={symbol}");
+    let parsed = Template::parse(&input).expect("parse Template; query placeholder");
+    let query: Vec<bool> = parsed.params.iter().map(|param| param.query).collect();
+    assert_eq!(query, [false, true]);
+
+    // 5. malformed placeholders are errors
+    for endpoint in [
+        "series/{}/observations",
+        "series/{series id}",
+        "series/{id: 32}",
+        "series/{id}/{id}",
+        "series/{id",
+        "series/id}",
+    ] {
+        let input = syn::LitStr::new(endpoint, proc_macro2::Span::call_site());
+        assert!(
+            Template::parse(&input).is_err(),
+            "{endpoint} should not parse"
+        );
+    }
+}
+
+// api/params.rs
+// =============
+//
+// Param
+#[test]
+fn parse_param() {
+    use kvapi_macros_internals::api::params::Param;
+
+    // 1. required
+    let parsed = syn::parse2::<Param>(quote! { series_id: &str }).expect("parse `name: Type`");
+    assert_eq!(parsed.key(), "series_id");
+    assert!(parsed.is_required());
+    assert!(parsed.style.is_none());
+
+    // 2. optional, with a collection style
+    let parsed = syn::parse2::<Param>(quote! { symbols: Option<Vec<String>> as json })
+        .expect("parse `name: Option<Type> as style`");
+    let parsed_type = parsed.ty;
+    assert_eq!(
+        quote!( #parsed_type ).to_string(),
+        "Option < Vec < String > >"
+    );
+    assert_eq!(parsed.style, Some(format_ident!("json")));
+
+    // 3. raw identifiers aren't part of the key
+    let parsed = syn::parse2::<Param>(quote! { r#type: Option<&str> }).expect("parse `r#type`");
+    assert_eq!(parsed.key(), "type");
+    assert!(!parsed.is_required());
+
+    // 4. unknown style
+    assert!(syn::parse2::<Param>(quote! { ids: Vec<u32> as csv }).is_err());
+
+    // 5. a setter can't be one of the builder's methods, in any case
+    for input in [
+        quote! { get: Option<u32> },
+        quote! { url: &str },
+        quote! { r#items: &str },
+        quote! { withTimeout: u64 },
+        quote! { get_with_meta: bool },
+    ] {
+        assert!(syn::parse2::<Param>(input.clone()).is_err(), "{input}");
+    }
+
+    // 6. nor another parameter's
+    use kvapi_macros_internals::api::dict::Entry;
+    let input = quote! { #[params(startTime: u64, start_time: u64)] "klines": Klines };
+    assert!(syn::parse2::<Entry>(input).is_err());
+    let input = quote! { #[params(startTime: u64), params(start_time: u64)] "klines": Klines };
+    assert!(syn::parse2::<Entry>(input).is_err());
+    let input = quote! { #[params(startTime: u64, endTime: u64)] "klines": Klines };
+    assert!(syn::parse2::<Entry>(input).is_ok());
+}
+
+// api/method.rs
+// =============
+//
+// Method
+#[test]
+fn parse_method() {
+    use kvapi_macros_internals::api::method::Method;
+
+    // 1. a single method
+    let parsed = Method::parse_list(syn::parse_quote!(DELETE)).expect("parse `DELETE`");
+    assert_eq!(parsed, vec![Method::Delete]);
+
+    // 2. lists, in either brackets
+    let input = syn::Expr::Verbatim(quote!([GET, delete, GET]));
+    let parsed = Method::parse_list(input).expect("parse `[..]`");
+    assert_eq!(parsed, vec![Method::Get, Method::Delete]);
+    let input = syn::Expr::Verbatim(quote!((PUT, PATCH, HEAD)));
+    let parsed = Method::parse_list(input).expect("parse `(..)`");
+    assert_eq!(parsed, vec![Method::Put, Method::Patch, Method::Head]);
+
+    // 3. unknown methods
+    assert!(Method::parse_list(syn::parse_quote!(FETCH)).is_err());
+
+    // 4. as a dict attr
+    use kvapi_macros_internals::api::dict::Entry;
+    let input = quote! {
+        #[method(GET, DELETE), rename: "cancel"]
+        "order/{id}": OrderAck
+    };
+    let parsed = syn::parse2::<Entry>(input).expect("parse Record; with attr (method)");
+    assert_eq!(parsed.methods, vec![Method::Get, Method::Delete]);
+}
+
+// api/body.rs
+// ===========
+//
+// Encoding, and `BodyType => ResponseType` entries
+#[test]
+fn parse_body() {
+    use kvapi_macros_internals::api::{body::Encoding, dict::Entry};
+
+    // 1. encodings
+    let parsed = Encoding::parse(syn::parse_quote!(form)).expect("parse `form`");
+    assert_eq!(parsed, Encoding::Form);
+    let parsed = Encoding::parse(syn::parse_quote!(bytes)).expect("parse `bytes`");
+    assert_eq!(parsed, Encoding::Bytes);
+    assert!(Encoding::parse(syn::parse_quote!(xml)).is_err());
+
+    // 2. typed body, json by default
+    let input = quote! {
+        "orders": CreateOrder => OrderAck
+    };
+    let parsed = syn::parse2::<Entry>(input).expect("parse Record; with body");
+    let (body, de_type) = (parsed.body, parsed.de_type);
+    assert_eq!(quote!( #body ).to_string(), "CreateOrder");
+    assert_eq!(quote!( #de_type ).to_string(), "OrderAck");
+    assert_eq!(parsed.encoding, Encoding::Json);
+
+    // 3. with attr (body)
+    let input = quote! {
+        #[body: form]
+        "token": std::collections::HashMap<String, String> => Token
+    };
+    let parsed = syn::parse2::<Entry>(input).expect("parse Record; with attr (body)");
+    let de_type = parsed.de_type;
+    assert_eq!(quote!( #de_type ).to_string(), "Token");
+    assert_eq!(parsed.encoding, Encoding::Form);
+
+    // 4. no body
+    let input = quote! {
+        "orders": OrderAck
+    };
+    let parsed = syn::parse2::<Entry>(input).expect("parse Record; no body");
+    assert!(parsed.body.is_none());
+}
+
+// api/envelope.rs
+// ===============
+//
+// Envelope, at the API & entry level
+#[test]
+fn parse_envelope() {
+    use kvapi_macros_internals::api::envelope::Envelope;
+
+    // 1. data only
+    let parsed = syn::parse2::<Envelope>(quote! { data: "observations" }).expect("parse data");
+    assert_eq!(parsed.data.value(), "observations");
+    assert!(parsed.error.is_none() && parsed.success.is_none());
+
+    // 2. with an error field
+    let input = quote! { data: "result", error: "error" };
+    let parsed = syn::parse2::<Envelope>(input).expect("parse `error`");
+    assert_eq!(
+        parsed.error.map(|error| error.value()),
+        Some("error".to_string())
+    );
+
+    // 3. with a success value
+    let input = quote! { data = "data", success: "code" == "200000", };
+    let parsed = syn::parse2::<Envelope>(input).expect("parse `success`");
+    let (field, value) = parsed.success.expect("success");
+    assert_eq!(field.value(), "code");
+    assert_eq!(quote!( #value ).to_string(), "\"200000\"");
+
+    // 4. invalid
+    assert!(syn::parse2::<Envelope>(quote! { error: "error" }).is_err());
+    assert!(syn::parse2::<Envelope>(quote! { data: "d", datum: "e" }).is_err());
+    let input = quote! { data: "d", error: "e", success: "code" == 0 };
+    assert!(syn::parse2::<Envelope>(input).is_err());
+
+    // 5. as a dict attr
+    use kvapi_macros_internals::api::dict::Entry;
+    let input = quote! {
+        #[envelope(data: "observations"), rename: "ten_yr"]
+        "series/observations": Vec<Observation>
+    };
+    let parsed = syn::parse2::<Entry>(input).expect("parse Record; with attr (envelope)");
+    assert_eq!(
+        parsed.envelope.expect("envelope").data.value(),
+        "observations"
+    );
+}
+
+// api/format.rs
+// =============
+//
+// Format; by the key's extension, or `#[format: xml]`
+#[test]
+fn parse_format() {
+    use kvapi_macros_internals::api::{dict::Entry, format::Format};
+
+    // 1. by extension
+    assert_eq!(Format::from_key("fred/series.csv"), Format::Csv);
+    assert_eq!(Format::from_key("config.yml"), Format::Yaml);
+    assert_eq!(Format::from_key("v1.2/series"), Format::Json);
+    assert_eq!(Format::from_key("index.html"), Format::Json);
+    assert_eq!(Format::from_key("data.csv?x=1"), Format::Csv);
+    assert_eq!(Format::from_key("data.csv?path=a/b.json"), Format::Csv);
+    assert_eq!(Format::from_key("data?file=x.csv"), Format::Json);
+
+    // 2. as a dict attr, over the extension
+    let input = quote! {
+        #[format: xml]
+        "feed.json": Feed
+    };
+    let parsed = syn::parse2::<Entry>(input).expect("parse Record; with attr (format)");
+    assert_eq!(parsed.format, Format::Xml);
+    let parsed = syn::parse2::<Entry>(quote! { "rates.toml": Rates }).expect("parse Record");
+    assert_eq!(parsed.format, Format::Toml);
+
+    // 3. invalid
+    assert!(syn::parse2::<Entry>(quote! { #[format: pdf] "report": Report }).is_err());
+    let input = quote! {
+        #[envelope(data: "rows")]
+        "rates.csv": Vec<Rate>
+    };
+    assert!(syn::parse2::<Entry>(input).is_err());
+}
+
+// api/builder.rs
+// ==============
+//
+// ApiBuilder; only `middleware` is checked, the rest is covered by `tests/api.rs`
+#[test]
+fn parse_builder() {
+    use kvapi_macros_internals::api::builder::ApiBuilder;
+
+    let input = quote! {
+        name:       Fred
+        middleware: [Logging, Signer::new("key", "secret"),]
+        dict:       { "series": Value }
+    };
+    let parsed = syn::parse2::<ApiBuilder>(input).expect("parse middleware");
+    let middleware: Vec<String> = parsed
+        .middleware
+        .iter()
+        .map(|middleware| quote!( #middleware ).to_string())
+        .collect();
+    assert_eq!(
+        middleware,
+        vec!["Logging", "Signer :: new (\"key\" , \"secret\")"]
+    );
+
+    let input = quote! {
+        name:       Fred
+        middleware: Logging
+        dict:       { "series": Value }
+    };
+    assert!(syn::parse2::<ApiBuilder>(input).is_err());
+}
+
+// ApiBuilder; `timeout:` & `connect_timeout:`, in milliseconds
+#[test]
+fn parse_timeout() {
+    use kvapi_macros_internals::api::builder::ApiBuilder;
+
+    let input = quote! {
+        name:               Fred
+        timeout:            10s
+        connect_timeout:    500ms
+        dict:               { "series": Value }
+    };
+    let parsed = syn::parse2::<ApiBuilder>(input).expect("parse timeouts");
+    assert_eq!(
+        (parsed.timeout, parsed.connect_timeout),
+        (Some(10_000), Some(500))
+    );
+    let input = quote! {
+        name:       Fred
+        timeout:    10
+        dict:       { "series": Value }
+    };
+    assert!(syn::parse2::<ApiBuilder>(input).is_err());
+}
+
+// api/auth.rs
+// ===========
+//
+// Auth; `Bearer`, `Basic`, or `ApiKey`
+#[test]
+fn parse_auth() {
+    use kvapi_macros_internals::api::auth::{Auth, Secret};
+
+    // 1. bearer, from an env var
+    let parsed = syn::parse2::<Auth>(quote! { Bearer(env "TOKEN") }).expect("parse Bearer");
+    let Auth::Bearer(Secret::Env(name)) = parsed else {
+        panic!("expected a bearer token from an env var");
+    };
+    assert_eq!(name.value(), "TOKEN");
+
+    // 2. basic, from expressions
+    let input = quote! { Basic(user, &password()) };
+    let parsed = syn::parse2::<Auth>(input).expect("parse Basic");
+    assert!(matches!(
+        parsed,
+        Auth::Basic(Secret::Expr(_), Secret::Expr(_))
+    ));
+
+    // 3. an API key, in any order
+    let input = quote! { ApiKey { name: "api_key", from: env "FRED_API", in: query } };
+    let parsed = syn::parse2::<Auth>(input).expect("parse ApiKey");
+    let Auth::ApiKey { location, name, .. } = parsed else {
+        panic!("expected an API key");
+    };
+    assert_eq!(
+        (location.to_string(), name.value()),
+        ("query".into(), "api_key".into())
+    );
+
+    // 4. OAuth2, with optional scopes & refresh token
+    let input = quote! {
+        OAuth2 {
+            token_url: "https://auth.example.com/token",
+            client_id: env "CLIENT_ID",
+            client_secret: env "CLIENT_SECRET",
+            scopes: ["read", "write"],
+        }
+    };
+    let parsed = syn::parse2::<Auth>(input).expect("parse OAuth2");
+    let Auth::OAuth2 {
+        scopes,
+        refresh_token,
+        ..
+    } = parsed
+    else {
+        panic!("expected OAuth2");
+    };
+    assert_eq!(scopes.len(), 2);
+    assert!(refresh_token.is_none());
+    let input = quote! { OAuth2 { token_url: url, client_id: id } };
+    assert!(syn::parse2::<Auth>(input).is_err());
+
+    // 5. invalid
+    assert!(syn::parse2::<Auth>(quote! { Digest("token") }).is_err());
+    assert!(syn::parse2::<Auth>(quote! { ApiKey { in: cookie, name: "k", from: key } }).is_err());
+    assert!(syn::parse2::<Auth>(quote! { ApiKey { in: header, name: "k" } }).is_err());
+    assert!(syn::parse2::<Auth>(quote! { ApiKey { in: header, name: "a b", from: key } }).is_err());
+}
+
+// api/rate_limit.rs
+// =================
+//
+// RateLimit; `requests/period`, with `#[weight]` per dict entry
+#[test]
+fn parse_rate_limit() {
+    use kvapi_macros_internals::api::{dict::Entry, rate_limit::RateLimit};
+
+    // 1. per period
+    let parsed = syn::parse2::<RateLimit>(quote! { 1200/min }).expect("parse per minute");
+    assert_eq!((parsed.capacity, parsed.seconds), (1200, 60));
+    let parsed = syn::parse2::<RateLimit>(quote! { 10/sec }).expect("parse per second");
+    assert_eq!((parsed.capacity, parsed.seconds), (10, 1));
+
+    // 2. weight of an entry; 1 by default
+    let input = quote! {
+        #[weight: 20]
+        "depth": OrderBook
+    };
+    let parsed = syn::parse2::<Entry>(input).expect("parse Record; with attr (weight)");
+    assert_eq!(parsed.weight, 20);
+    let parsed = syn::parse2::<Entry>(quote! { "ping": Value }).expect("parse Record");
+    assert_eq!(parsed.weight, 1);
+
+    // 3. invalid
+    assert!(syn::parse2::<RateLimit>(quote! { 10/fortnight }).is_err());
+    assert!(syn::parse2::<RateLimit>(quote! { 0/sec }).is_err());
+    assert!(syn::parse2::<RateLimit>(quote! { 10 }).is_err());
+    assert!(syn::parse2::<Entry>(quote! { #[weight: heavy] "depth": OrderBook }).is_err());
+}
+
+// api/retry.rs
+// ============
+//
+// Retry; `attempts`, `backoff`, `on`, and `non_idempotent`; and timeouts
+#[test]
+fn parse_retry() {
+    use kvapi_macros_internals::api::{
+        dict::Entry,
+        retry::{Backoff, On, Retry},
+    };
+
+    // 1. in full
+    let input = quote! {
+        attempts: 5,
+        backoff: exponential(200ms, 10s),
+        on: [429, 500..=599, timeout, connect],
+        non_idempotent: true,
+    };
+    let parsed = syn::parse2::<Retry>(input).expect("parse Retry");
+    assert_eq!(parsed.attempts, 5);
+    assert_eq!(parsed.backoff, Some(Backoff::Exponential(200, 10_000)));
+    assert_eq!(
+        parsed.on,
+        Some(vec![
+            On::Status(429, 429),
+            On::Status(500, 599),
+            On::Timeout,
+            On::Connect
+        ])
+    );
+    assert!(parsed.non_idempotent);
+
+    // 2. defaults
+    let parsed = syn::parse2::<Retry>(quote! { attempts: 3 }).expect("parse Retry");
+    assert!(parsed.backoff.is_none() && parsed.on.is_none() && !parsed.non_idempotent);
+    assert!(parsed.max_wait.is_none());
+    let parsed = syn::parse2::<Retry>(quote! { attempts: 3, max_wait: 30s }).expect("parse Retry");
+    assert_eq!(parsed.max_wait, Some(30_000));
+
+    // 3. as a dict attr
+    let input = quote! {
+        #[retry(attempts: 2, backoff: constant(1m))]
+        "orders": Orders
+    };
+    let parsed = syn::parse2::<Entry>(input).expect("parse Record; with attr (retry)");
+    let retry = parsed.retry.expect("an entry's retry policy");
+    assert_eq!(retry.backoff, Some(Backoff::Constant(60_000)));
+
+    // 4. a timeout, as a dict attr
+    let parsed = syn::parse2::<Entry>(quote! { #[timeout: 1500ms] "ticker": Ticker })
+        .expect("parse Record; with attr (timeout)");
+    assert_eq!(parsed.timeout, Some(1500));
+    assert!(syn::parse2::<Entry>(quote! { #[timeout: 2] "ticker": Ticker }).is_err());
+
+    // 5. invalid
+    assert!(syn::parse2::<Retry>(quote! { backoff: constant(1s) }).is_err());
+    assert!(syn::parse2::<Retry>(quote! { attempts: 3, backoff: constant(1) }).is_err());
+    assert!(syn::parse2::<Retry>(quote! { attempts: 3, backoff: linear(1s) }).is_err());
+    assert!(syn::parse2::<Retry>(quote! { attempts: 3, on: [reset] }).is_err());
+    assert!(syn::parse2::<Retry>(quote! { attempts: 3, on: [599..=500] }).is_err());
+}
+
+#[test]
+fn parse_cache() {
+    use kvapi_macros_internals::api::{cache::Cache, dict::Entry};
+
+    // 1. a ttl, in memory
+    let parsed = syn::parse2::<Cache>(quote! { 10m }).expect("parse Cache");
+    assert_eq!(parsed.ttl, Some(600_000));
+    assert!(parsed.store.is_none());
+
+    // 2. a ttl & store
+    let input = quote! { { ttl: 1h, store: kvapi::cache::Disk::new(".cache") } };
+    let parsed = syn::parse2::<Cache>(input).expect("parse Cache; with store");
+    assert_eq!(parsed.ttl, Some(3_600_000));
+    let store = parsed.store.expect("a store");
+    assert_eq!(
+        quote!(#store).to_string(),
+        quote!(kvapi::cache::Disk::new(".cache")).to_string()
+    );
+
+    // 3. as a dict attr
+    let parsed = syn::parse2::<Entry>(quote! { #[cache: 1d] "tickers.json": Tickers })
+        .expect("parse Record; with attr (cache)");
+    assert_eq!(parsed.cache, Some(86_400_000));
+
+    // 4. invalid
+    assert!(syn::parse2::<Cache>(quote! { 10 }).is_err());
+    assert!(syn::parse2::<Cache>(quote! { { size: 10 } }).is_err());
+    assert!(syn::parse2::<Entry>(quote! { #[cache: forever] "tickers.json": Tickers }).is_err());
+}
+
+#[test]
+fn parse_paginate() {
+    use kvapi_macros_internals::api::{
+        dict::Entry,
+        paginate::{By, Paginate},
+    };
+
+    // 1. by offset
+    let input = quote! { offset = "offset", limit = "limit", size = 1000, total = "count", items = "seriess" };
+    let parsed = syn::parse2::<Paginate>(input).expect("parse Paginate");
+    assert_eq!(
+        parsed.by,
+        By::Offset {
+            offset: "offset".into(),
+            limit: Some("limit".into()),
+            size: Some(1000),
+            total: Some("count".into()),
+        }
+    );
+    assert_eq!(parsed.items.as_deref(), Some("seriess"));
+    assert!(parsed.item.is_none());
+
+    // 2. by cursor, or link
+    let input = quote! { cursor: "cursor", next: "meta/next_cursor", items: "data", item: Trade };
+    let parsed = syn::parse2::<Paginate>(input).expect("parse Paginate; by cursor");
+    assert_eq!(
+        parsed.by,
+        By::Cursor {
+            cursor: "cursor".into(),
+            next: "meta/next_cursor".into()
+        }
+    );
+    assert!(parsed.item.is_some());
+    let parsed = syn::parse2::<Paginate>(quote! { link }).expect("parse Paginate; by link");
+    assert_eq!(parsed.by, By::Link);
+
+    // 3. as a dict attr
+    let input = quote! {
+        #[paginate(offset = "offset", items = "seriess")]
+        "category/series": CategorySeries
+    };
+    let parsed = syn::parse2::<Entry>(input).expect("parse Record; with attr (paginate)");
+    assert!(parsed.paginate.is_some());
+    let input = quote! { #[paginate(link)] "history": Vec<Trade> };
+    assert!(syn::parse2::<Entry>(input).is_ok());
+
+    // 4. invalid
+    assert!(syn::parse2::<Paginate>(quote! { items = "data" }).is_err());
+    assert!(syn::parse2::<Paginate>(quote! { offset = "offset", link }).is_err());
+    assert!(syn::parse2::<Paginate>(quote! { cursor = "cursor" }).is_err());
+    assert!(syn::parse2::<Paginate>(quote! { link, total = "count" }).is_err());
+    assert!(syn::parse2::<Paginate>(quote! { offset = "offset", size = 10 }).is_err());
+    assert!(syn::parse2::<Paginate>(quote! { page = "page" }).is_err());
+    let input = quote! { #[method: POST, paginate(link)] "orders": Orders };
+    assert!(syn::parse2::<Entry>(input).is_err());
+}
+
+#[test]
+fn parse_ws() {
+    use kvapi_macros_internals::api::{retry::Backoff, ws::Ws};
+
+    // 1. templates, a ping, a backoff & streams
+    let input = quote! { {
+        url: "wss://stream.binance.com:9443/ws",
+        subscribe: r#"{"method": "SUBSCRIBE", "params": ["{stream}"], "id": 1}"#,
+        unsubscribe: r#"{"method": "UNSUBSCRIBE", "params": ["{stream}"], "id": 1}"#,
+        ping: 3m,
+        reconnect: exponential(500ms, 30s),
+        streams: {
+            "{symbol}@trade": Trade,
+            "btcusdt@bookTicker": BookTicker,
+            "{symbol}@kline_{interval}": Kline,
+            "!miniTicker@arr": Vec<MiniTicker>,
+        },
+    } };
+    let parsed = syn::parse2::<Ws>(input).expect("parse Ws");
+    assert_eq!(parsed.url, "wss://stream.binance.com:9443/ws");
+    assert!(parsed.subscribe.unwrap().contains("{stream}"));
+    assert!(parsed.unsubscribe.is_some());
+    assert_eq!(parsed.ping, Some((180_000, None)));
+    assert_eq!(parsed.reconnect, Some(Backoff::Exponential(500, 30_000)));
+    let fields: Vec<String> = parsed.streams.iter().map(|s| s.field.to_string()).collect();
+    assert_eq!(fields, ["trade", "btcusdt_book_ticker", "kline", "mini_ticker_arr"]);
+    assert_eq!(parsed.streams[2].key, "{symbol}@kline_{interval}");
+    assert_eq!(parsed.streams[2].template.params.len(), 2);
+
+    // 2. a ping message
+    let input = quote! { {
+        url: "wss://stream.bybit.com/v5/public/spot",
+        ping: { every: 20s, message: r#"{"op": "ping"}"# },
+        streams: { "tickers.{symbol}": Ticker },
+    } };
+    let parsed = syn::parse2::<Ws>(input).expect("parse Ws; with ping message");
+    assert_eq!(parsed.ping, Some((20_000, Some(r#"{"op": "ping"}"#.to_string()))));
+    assert_eq!(parsed.streams[0].field.to_string(), "tickers");
+
+    // 3. invalid
+    assert!(syn::parse2::<Ws>(quote! { { streams: { "trade": Trade } } }).is_err());
+    assert!(syn::parse2::<Ws>(quote! { { url: "wss://a" } }).is_err());
+    assert!(syn::parse2::<Ws>(quote! { { url: "wss://a", streams: { "{symbol}": Trade } } }).is_err());
+    assert!(syn::parse2::<Ws>(quote! { { url: "wss://a", streams: { "a@trade": A, "a.trade": B } } }).is_err());
+    assert!(syn::parse2::<Ws>(quote! { { url: "wss://a", unsubscribe: "x", streams: { "trade": T } } }).is_err());
+    assert!(syn::parse2::<Ws>(quote! { { url: "wss://a", ping: { message: "ping" }, streams: { "trade": T } } }).is_err());
+    assert!(syn::parse2::<Ws>(quote! { { url: "wss://a", heartbeat: 1s, streams: { "trade": T } } }).is_err());
 }